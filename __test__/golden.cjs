@@ -0,0 +1,30 @@
+// Regenerates golden.json, the SHA-256 of every composited RGBA frame of the fixtures in gifs/
+// Only run it after checking the new frames are correct, e.g. with visual_preview.cjs,
+// frames differing from the independent compositor in reference.cjs are reported
+const { Decoder } = require('../index')
+const { compositeFrames } = require('./reference.cjs')
+const { createHash } = require('crypto')
+const { readdirSync, readFileSync, writeFileSync } = require('fs')
+
+const golden = {}
+for (const file of readdirSync('./gifs').sort()) {
+  try {
+    const gif = Decoder.decodePath(`./gifs/${file}`)
+    const buffers = gif.decodeFrames({
+      implementDisposalPrevious: true,
+      storeCache: false,
+      disableDisposalMethods: false,
+      rawDecode: false,
+    })
+    golden[file] = buffers.map((buffer) => createHash('sha256').update(buffer).digest('hex'))
+    const reference = compositeFrames(readFileSync(`./gifs/${file}`))
+    buffers.forEach((buffer, index) => {
+      if (reference[index] === undefined || !buffer.equals(reference[index])) {
+        console.error(`${file}: frame ${index} differs from reference.cjs`)
+      }
+    })
+  } catch (error) {
+    console.error(`Skipping ${file}: ${error.message}`)
+  }
+}
+writeFileSync('./__test__/golden.json', JSON.stringify(golden, null, 2) + '\n')
@@ -1,7 +1,10 @@
 import test from 'ava'
 
-import { readFileSync } from 'fs'
-import { Decoder } from '../index'
+import { createHash } from 'crypto'
+import { getEventListeners } from 'events'
+import { readdirSync, readFileSync } from 'fs'
+import { Decoder, StreamingDecoder } from '../index'
+import { compositeFrames } from './reference.cjs'
 
 test('sample_1.gif: Version is 89a', (t) => {
   const gif = Decoder.decodePath('./gifs/sample_1.gif')
@@ -85,3 +88,808 @@ test('Dancing.gif: Global Table Length is 256', (t) => {
   const gif = Decoder.decodePath('./gifs/Dancing.gif')
   t.is(gif.globalTable.length, 256)
 })
+
+test('sample_2_animation.gif: Loops forever', (t) => {
+  const gif = Decoder.decodePath('./gifs/sample_2_animation.gif')
+  t.is(gif.loopCount, 0)
+})
+
+test('sample_1.gif: Has no loop count', (t) => {
+  const gif = Decoder.decodePath('./gifs/sample_1.gif')
+  t.is(gif.loopCount, null)
+})
+
+test('pepeMeltdown.gif: Comment is preserved', (t) => {
+  const gif = Decoder.decodePath('./gifs/pepeMeltdown.gif')
+  t.is(gif.comments.length, 1)
+  t.is(gif.comments[0].frameIndex, 0)
+  t.is(gif.comments[0].text, 'Resized with ezgif.com GIF maker')
+})
+
+test('plain_text.gif: Plain Text Extension is attached to its frame', (t) => {
+  const gif = Decoder.decodePath('./gifs/plain_text.gif')
+  t.is(gif.frames.length, 1)
+  t.is(gif.frames[0].plainText?.text, 'Hi')
+  t.is(gif.frames[0].plainText?.characterCellWidth, 8)
+})
+
+test('plain_text.gif: Plain Text Extension is rendered', (t) => {
+  const gif = Decoder.decodePath('./gifs/plain_text.gif')
+  const buffer = gif.decodeFrame(0, {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+    renderPlainText: true,
+  })
+  // Top left pixel of 'H' is foreground, the pixel after its left stem is background
+  t.deepEqual([...buffer.subarray(0, 4)], [255, 255, 255, 255])
+  t.deepEqual([...buffer.subarray(8, 12)], [0, 0, 0, 255])
+})
+
+test('BBoomer.gif: Application Extensions are kept', (t) => {
+  const gif = Decoder.decodePath('./gifs/BBoomer.gif')
+  t.deepEqual(
+    gif.applicationExtensions.map((extension) => extension.identifier + extension.authenticationCode),
+    ['NETSCAPE2.0', 'XMP DataXMP'],
+  )
+  t.deepEqual(gif.applicationExtensions[0].data, [1, 0, 0])
+})
+
+test('Application Extensions with invalid UTF-8 identifiers are decoded', (t) => {
+  const contents = readFileSync('./gifs/gif87a.gif')
+  const identifiers = [Buffer.alloc(11, 0xff), Buffer.from('ABCDEFG\xC3\xA9AB', 'latin1')]
+  const extensions = identifiers.map((identifier) =>
+    Buffer.concat([Buffer.from([0x21, 0xff, 0x0b]), identifier, Buffer.from([0x00])]),
+  )
+  const gif = Decoder.decodeBuffer(Buffer.concat([contents.subarray(0, 34), ...extensions, contents.subarray(34)]))
+  t.deepEqual(
+    gif.applicationExtensions.map((extension) => [extension.identifier, extension.authenticationCode]),
+    [
+      ['\uFFFD'.repeat(8), '\uFFFD'.repeat(3)],
+      ['ABCDEFG\uFFFD', '\uFFFDAB'],
+    ],
+  )
+})
+
+test('BBoomer.gif: XMP packet is decoded', (t) => {
+  const gif = Decoder.decodePath('./gifs/BBoomer.gif')
+  const xmp = gif.applicationExtensions[1].xmp ?? ''
+  t.true(xmp.startsWith('<?xpacket begin='))
+  t.true(xmp.endsWith('<?xpacket end="r"?>'))
+})
+
+test('icc_profile.gif: ICC profile is exposed', (t) => {
+  const gif = Decoder.decodePath('./gifs/icc_profile.gif')
+  t.is(gif.iccProfile?.length, 312)
+  t.is(Decoder.decodePath('./gifs/sample_1.gif').iccProfile, null)
+})
+
+test('icc_profile.gif: Colours are converted into sRGB', (t) => {
+  const gif = Decoder.decodePath('./gifs/icc_profile.gif')
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  t.deepEqual([...gif.decodeFrame(0, decoderOptions)], [128, 128, 128, 255, 255, 0, 0, 255])
+  // The profile has a linear tone curve with sRGB primaries
+  t.deepEqual(
+    [...gif.decodeFrame(0, { ...decoderOptions, convertToSrgb: true })],
+    [188, 188, 188, 255, 255, 0, 0, 255],
+  )
+})
+
+test('gif87a.gif: Image without a Graphic Control Extension is decoded', (t) => {
+  const gif = Decoder.decodePath('./gifs/gif87a.gif')
+  t.is(gif.version, '87a')
+  t.is(gif.frames.length, 1)
+  t.is(gif.frames[0].gcd, undefined)
+  t.deepEqual(
+    [
+      ...gif.decodeFrame(0, {
+        implementDisposalPrevious: true,
+        storeCache: false,
+        disableDisposalMethods: false,
+        rawDecode: false,
+      }),
+    ],
+    [0, 0, 0, 255, 255, 255, 255, 255],
+  )
+})
+
+test('orphaned_gce.gif: Orphaned Graphic Control Extensions are reported', (t) => {
+  const gif = Decoder.decodePath('./gifs/orphaned_gce.gif')
+  t.is(gif.frames.length, 1)
+  t.is(gif.frames[0].gcd?.delayTime, 20)
+  t.deepEqual(
+    gif.orphanedGcds.map((gcd) => gcd.delayTime),
+    [10, 30],
+  )
+})
+
+test('EmptyGif.gif: Truncated files throw ERR_GIF_TRUNCATED_BLOCK', (t) => {
+  t.throws(() => Decoder.decodePath('./gifs/EmptyGif.gif'), {
+    code: 'ERR_GIF_TRUNCATED_BLOCK',
+    message: 'Unable to get signature at offset 0, the file is corrupted',
+  })
+  const contents = readFileSync('./gifs/gif87a.gif')
+  t.throws(() => Decoder.decodeBuffer(contents.subarray(0, 27)), {
+    code: 'ERR_GIF_TRUNCATED_BLOCK',
+    message: 'Unable to get image_height at offset 26 in frame 0, the file is corrupted',
+  })
+})
+
+test('LZW minimum code sizes larger than 11 are rejected', (t) => {
+  const contents = readFileSync('./gifs/gif87a.gif')
+  for (const codeSize of [12, 31, 255]) {
+    const corrupted = Buffer.from(contents)
+    corrupted[29] = codeSize
+    t.throws(() => Decoder.decodeBuffer(corrupted, { lazy: true }), {
+      code: 'ERR_GIF_INVALID_LZW_MINIMUM_CODE_SIZE',
+      message: `Invalid LZW minimum code size ${codeSize} at offset 29 in frame 0`,
+    })
+  }
+})
+
+test('Errors carry a stable code', (t) => {
+  t.throws(() => Decoder.decodeBuffer(Buffer.from('\x89PNG\r\n\x1a\n')), {
+    code: 'ERR_GIF_BAD_SIGNATURE',
+  })
+  t.throws(() => Decoder.decodePath('./gifs/does_not_exist.gif'), { code: 'ERR_GIF_IO' })
+  const gif = Decoder.decodePath('./gifs/gif87a.gif')
+  t.throws(
+    () =>
+      gif.decodeFrame(1, {
+        implementDisposalPrevious: true,
+        storeCache: false,
+        disableDisposalMethods: false,
+        rawDecode: false,
+      }),
+    { code: 'ERR_GIF_FRAME_OUT_OF_BOUNDS', message: 'Frame index 1 out of bounds, the gif has 1 frames' },
+  )
+})
+
+test('Errors carry the offset and frame index', async (t) => {
+  const truncated = readFileSync('./gifs/gif87a.gif').subarray(0, 27)
+  for (const error of [
+    t.throws(() => Decoder.decodeBuffer(truncated)),
+    await t.throwsAsync(Decoder.decodeBufferAsync(truncated)),
+  ]) {
+    t.like(error, { code: 'ERR_GIF_TRUNCATED_BLOCK', offset: 26, frameIndex: 0 })
+  }
+  const badSignature = t.throws(() => Decoder.decodeBuffer(Buffer.from('\x89PNG\r\n\x1a\n')))
+  t.like(badSignature, { code: 'ERR_GIF_BAD_SIGNATURE', offset: 0 })
+  t.false('frameIndex' in (badSignature ?? {}))
+})
+
+test('Unsupported versions are rejected unless strict is disabled', (t) => {
+  const contents = Buffer.from(readFileSync('./gifs/gif87a.gif'))
+  contents.write('90a', 3, 'latin1')
+  t.throws(() => Decoder.decodeBuffer(contents), {
+    code: 'ERR_GIF_UNSUPPORTED_VERSION',
+    message: 'Unsupported version 90a at offset 3',
+  })
+  const gif = Decoder.decodeBuffer(contents, { strict: false })
+  t.is(gif.version, '90a')
+  t.is(gif.frames.length, 1)
+})
+
+test('Unknown extensions are skipped and recorded', (t) => {
+  const contents = readFileSync('./gifs/gif87a.gif')
+  const extension = Buffer.from([0x21, 0x99, 0x03, 0x01, 0x02, 0x03, 0x00])
+  const gif = Decoder.decodeBuffer(Buffer.concat([contents.subarray(0, 34), extension, contents.subarray(34)]))
+  t.is(gif.frames.length, 1)
+  t.deepEqual(
+    gif.unknownExtensions.map((extension) => [extension.offset, extension.label, [...extension.data]]),
+    [[34, 0x99, [1, 2, 3]]],
+  )
+})
+
+test('Unknown block introducers are rejected unless strict is disabled', (t) => {
+  const contents = readFileSync('./gifs/gif87a.gif')
+  const garbage = Buffer.concat([contents.subarray(0, 34), Buffer.from([0x00, 0x42]), contents.subarray(34)])
+  t.throws(() => Decoder.decodeBuffer(garbage), {
+    code: 'ERR_GIF_UNKNOWN_BLOCK',
+    message: 'Unknown block introducer 0x42 at offset 35',
+  })
+  t.is(Decoder.decodeBuffer(garbage, { strict: false }).frames.length, 1)
+  const padded = Buffer.concat([contents.subarray(0, 34), Buffer.from([0x00, 0x00]), contents.subarray(34)])
+  t.is(Decoder.decodeBuffer(padded).frames.length, 1)
+})
+
+test('StreamingDecoder: Emits blocks as soon as their data has arrived', (t) => {
+  const contents = readFileSync('./gifs/BBoomer.gif')
+  const decoder = new StreamingDecoder()
+  const events = []
+  for (let i = 0; i < contents.length; i += 7) {
+    events.push(...decoder.push(contents.subarray(i, i + 7)))
+  }
+  const expected = Decoder.decodePath('./gifs/BBoomer.gif')
+  t.is(events[0].kind, 'header')
+  t.deepEqual(events[0].lsd, expected.lsd)
+  t.deepEqual(
+    events.filter((event) => event.kind === 'applicationExtension').map((event) => event.applicationExtension?.identifier),
+    ['NETSCAPE', 'XMP Data'],
+  )
+  const frames = events.filter((event) => event.kind === 'frame')
+  t.deepEqual(
+    frames.map((event) => event.frameIndex),
+    expected.frames.map((_, i) => i),
+  )
+  t.deepEqual([...(frames[1].frame?.indexStream ?? [])], [...expected.frames[1].indexStream])
+  t.is(events[events.length - 1].kind, 'end')
+  const gif = decoder.finish()
+  t.is(gif.frames.length, expected.frames.length)
+  t.is(gif.loopCount, expected.loopCount)
+})
+
+test('StreamingDecoder: Header is emitted before the frames arrive', (t) => {
+  const contents = readFileSync('./gifs/sample_2_animation.gif')
+  const decoder = new StreamingDecoder()
+  t.deepEqual(decoder.push(contents.subarray(0, 10)), [])
+  t.deepEqual(
+    decoder.push(contents.subarray(10, 13 + 3 * 8)).map((event) => event.kind),
+    ['header'],
+  )
+  t.throws(() => decoder.finish(), { code: 'ERR_GIF_TRUNCATED_BLOCK' })
+  t.throws(() => new StreamingDecoder().push(Buffer.from('\x89PNG\r\n')), { code: 'ERR_GIF_BAD_SIGNATURE' })
+})
+
+test('Lazy parsing decompresses index streams on demand', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const expected = Decoder.decodePath('./gifs/forsenEnter.gif')
+  const gif = Decoder.decodePath('./gifs/forsenEnter.gif', { lazy: true })
+  t.true(gif.frames.every((frame) => frame.indexStream.length === 0))
+  t.deepEqual([...gif.indexStream(3)], [...expected.frames[3].indexStream])
+  t.deepEqual([...gif.decodeFrame(0, decoderOptions)], [...expected.decodeFrame(0, decoderOptions)])
+  // The frames are the ones parsed, the index streams decompressed since then are only read with indexStream
+  t.true(gif.frames.every((frame) => frame.indexStream.length === 0))
+
+  const frames = gif.decodeFrames({ ...decoderOptions, freeIndexStreams: true })
+  t.true(gif.frames.every((frame) => frame.indexStream.length === 0))
+  t.deepEqual(
+    frames.map((frame) => [...frame]),
+    expected.decodeFrames(decoderOptions).map((frame) => [...frame]),
+  )
+})
+
+test('Probing reads the summary without decoding', (t) => {
+  const gif = Decoder.decodePath('./gifs/sample_2_animation.gif')
+  const expected = {
+    width: 11,
+    height: 29,
+    frameCount: 3,
+    totalDuration: gif.frames.reduce((duration, frame) => duration + (frame.gcd?.delayTime ?? 0) * 10, 0),
+    loopCount: 0,
+  }
+  t.true(expected.totalDuration > 0)
+  t.deepEqual(Decoder.probePath('./gifs/sample_2_animation.gif'), expected)
+  t.deepEqual(Decoder.probeBuffer(readFileSync('./gifs/sample_2_animation.gif')), expected)
+  t.is(Decoder.probePath('./gifs/sample_1.gif').loopCount, undefined)
+  t.throws(() => Decoder.probePath('./gifs/EmptyGif.gif'), { code: 'ERR_GIF_TRUNCATED_BLOCK' })
+
+  // 7000 frames of 1x1 with the longest delay, 655.35 seconds each
+  const frame = [
+    ...[0x21, 0xf9, 0x04, 0x00, 0xff, 0xff, 0x00, 0x00],
+    ...[0x2c, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00],
+  ]
+  const endless = Buffer.from([
+    ...Buffer.from('GIF89a'),
+    ...[0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00],
+    ...Array.from({ length: 7000 }, () => frame).flat(),
+    0x3b,
+  ])
+  t.is(Decoder.probeBuffer(endless).totalDuration, 4294967295)
+})
+
+test('Async variants resolve to the same results', async (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const expected = Decoder.decodePath('./gifs/sample_2_animation.gif')
+  const gif = await Decoder.decodePathAsync('./gifs/sample_2_animation.gif')
+  t.is(gif.frames.length, expected.frames.length)
+  const fromBuffer = await Decoder.decodeBufferAsync(readFileSync('./gifs/sample_2_animation.gif'))
+  t.deepEqual(fromBuffer.lsd, expected.lsd)
+  const frames = await gif.decodeFramesAsync(decoderOptions)
+  t.deepEqual(
+    frames.map((frame) => [...frame]),
+    expected.decodeFrames(decoderOptions).map((frame) => [...frame]),
+  )
+  t.deepEqual([...(await gif.decodeFrameAsync(2, decoderOptions))], [...expected.decodeFrame(2, decoderOptions)])
+})
+
+test('Async variants reject with the error code', async (t) => {
+  await t.throwsAsync(Decoder.decodePathAsync('./gifs/EmptyGif.gif'), { code: 'ERR_GIF_TRUNCATED_BLOCK' })
+  await t.throwsAsync(Decoder.decodeBufferAsync(Buffer.from('\x89PNG\r\n')), { code: 'ERR_GIF_BAD_SIGNATURE' })
+  const gif = Decoder.decodePath('./gifs/gif87a.gif')
+  await t.throwsAsync(
+    gif.decodeFrameAsync(1, {
+      implementDisposalPrevious: true,
+      storeCache: false,
+      disableDisposalMethods: false,
+      rawDecode: false,
+    }),
+    { code: 'ERR_GIF_FRAME_OUT_OF_BOUNDS' },
+  )
+})
+
+test('Parallel decompression produces the same index streams', (t) => {
+  for (let gif_test_case of gif_test_cases) {
+    const expected = Decoder.decodePath(gif_test_case.file)
+    const gif = Decoder.decodePath(gif_test_case.file, { parallel: true })
+    t.deepEqual(
+      gif.frames.map((frame) => [...frame.indexStream]),
+      expected.frames.map((frame) => [...frame.indexStream]),
+    )
+  }
+})
+
+test('Memory mapped files decode like read ones', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const expected = Decoder.decodePath('./gifs/pepeMeltdown.gif')
+  const gif = Decoder.decodePath('./gifs/pepeMeltdown.gif', { memoryMap: true, lazy: true })
+  t.deepEqual([...gif.decodeFrame(4, decoderOptions)], [...expected.decodeFrame(4, decoderOptions)])
+  t.is(Decoder.probePath('./gifs/pepeMeltdown.gif', { memoryMap: true }).frameCount, 10)
+  // Empty files can not be mapped and fall back to reading
+  t.throws(() => Decoder.decodePath('./gifs/EmptyGif.gif', { memoryMap: true }), { code: 'ERR_GIF_TRUNCATED_BLOCK' })
+  t.throws(() => Decoder.decodePath('./gifs/does_not_exist.gif', { memoryMap: true }), { code: 'ERR_GIF_IO' })
+})
+
+test('Lazy buffers are read in place instead of copied', async (t) => {
+  const expected = Decoder.decodePath('./gifs/clap.gif')
+  const contents = readFileSync('./gifs/clap.gif')
+  // Decode from a view into a larger allocation
+  const buffer = Buffer.concat([Buffer.alloc(7), contents]).subarray(7)
+  const gif = Decoder.decodeBuffer(buffer, { lazy: true })
+  const fromAsync = await Decoder.decodeBufferAsync(buffer, { lazy: true })
+  t.deepEqual([...gif.indexStream(1)], [...expected.frames[1].indexStream])
+  t.deepEqual([...fromAsync.indexStream(1)], [...expected.frames[1].indexStream])
+
+  buffer.fill(0)
+  t.notDeepEqual([...gif.indexStream(0)], [...expected.frames[0].indexStream])
+})
+
+test('Frame iterator yields the same frames as decodeFrames', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  for (let gif_test_case of gif_test_cases) {
+    const expected = Decoder.decodePath(gif_test_case.file).decodeFrames(decoderOptions)
+    const gif = Decoder.decodePath(gif_test_case.file)
+    const frames = []
+    for (const frame of gif.iterFrames({ ...decoderOptions, storeCache: true })) {
+      frames.push([...frame])
+    }
+    t.deepEqual(
+      frames,
+      expected.map((frame) => [...frame]),
+    )
+    t.true(gif.frames.every((_, index) => !gif.cachedFrame(index)))
+  }
+})
+
+test('Frame iterator frees lazy index streams as it goes', (t) => {
+  const gif = Decoder.decodePath('./gifs/forsenEnter.gif', { lazy: true })
+  const iterator = gif.iterFrames({
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+    freeIndexStreams: true,
+  })
+  t.is(iterator.next().done, false)
+  t.is(iterator.next().done, false)
+  t.true(gif.frames.every((frame) => frame.indexStream.length === 0))
+  t.is([...iterator].length, gif.frames.length - 2)
+  t.deepEqual(iterator.next(), { done: true })
+})
+
+test('Decoder limits are checked before decompressing', (t) => {
+  t.throws(() => Decoder.decodePath('./gifs/sample_2_animation.gif', { limits: { maxWidth: 10 } }), {
+    code: 'ERR_GIF_LIMITS_EXCEEDED',
+    message: 'Decoder limit max_width exceeded at offset 6',
+  })
+  t.throws(() => Decoder.decodePath('./gifs/sample_2_animation.gif', { limits: { maxFrames: 2 } }), {
+    code: 'ERR_GIF_LIMITS_EXCEEDED',
+    message: 'in frame 2',
+  })
+  t.throws(() => Decoder.decodePath('./gifs/sample_2_animation.gif', { limits: { maxDecodedBytes: 11 * 29 * 4 } }), {
+    code: 'ERR_GIF_LIMITS_EXCEEDED',
+    message: 'Decoder limit max_decoded_bytes',
+  })
+  const gif = Decoder.decodePath('./gifs/sample_2_animation.gif', {
+    limits: { maxWidth: 11, maxHeight: 29, maxPixels: 11 * 29, maxFrames: 3, maxCompressionRatio: 1000 },
+  })
+  t.is(gif.frames.length, 3)
+
+  // 65535x65535 frame made of a single byte of image data
+  const bomb = Buffer.from([
+    ...Buffer.from('GIF89a'),
+    ...[0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00],
+    ...[0x2c, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00],
+    ...[0x02, 0x01, 0x44, 0x00, 0x3b],
+  ])
+  t.throws(() => Decoder.decodeBuffer(bomb, { limits: { maxPixels: 1 << 24 } }), {
+    code: 'ERR_GIF_LIMITS_EXCEEDED',
+    message: 'Decoder limit max_pixels exceeded at offset 6',
+  })
+  t.throws(() => Decoder.probeBuffer(bomb, { limits: { maxCompressionRatio: 1000 } }), {
+    code: 'ERR_GIF_LIMITS_EXCEEDED',
+    message: 'Decoder limit max_compression_ratio exceeded at offset 13 in frame 0',
+  })
+
+  // 2000x2000 screen with 100 frames of 1x1, every frame is composited onto a 16 MB canvas
+  const frame = [0x2c, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00]
+  const tiny = Buffer.from([
+    ...Buffer.from('GIF89a'),
+    ...[0xd0, 0x07, 0xd0, 0x07, 0x00, 0x00, 0x00],
+    ...Array.from({ length: 100 }, () => frame).flat(),
+    0x3b,
+  ])
+  t.is(Decoder.decodeBuffer(tiny).frames.length, 100)
+  t.throws(() => Decoder.decodeBuffer(tiny, { limits: { maxDecodedBytes: 20_000_000 } }), {
+    code: 'ERR_GIF_LIMITS_EXCEEDED',
+    message: 'Decoder limit max_decoded_bytes exceeded at offset 28 in frame 1',
+  })
+})
+
+test('Async decodes report progress and can be aborted', async (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const parsed = []
+  const gif = await Decoder.decodePathAsync('./gifs/forsenParty.gif', undefined, {
+    onProgress: (progress) => parsed.push(progress),
+  })
+  const composited = []
+  await gif.decodeFramesAsync(decoderOptions, { onProgress: (progress) => composited.push(progress) })
+  // Progress is delivered through the event loop, possibly after the promise resolved
+  await new Promise((resolve) => setImmediate(resolve))
+  t.is(parsed.length, 60)
+  t.is(parsed[59].frames, 60)
+  t.is(parsed[59].frameCount, undefined)
+  t.true(parsed[59].bytes <= parsed[59].totalBytes)
+  t.is(composited.length, 60)
+  t.deepEqual(
+    composited.map((progress) => progress.frames),
+    [...Array(60).keys()].map((index) => index + 1),
+  )
+  t.is(composited[59].frameCount, 60)
+  t.is(composited[59].bytes, composited[59].totalBytes)
+
+  const aborted = new AbortController()
+  aborted.abort()
+  await t.throwsAsync(Decoder.decodeBufferAsync(readFileSync('./gifs/forsenParty.gif'), undefined, { signal: aborted.signal }), {
+    code: 'ERR_GIF_CANCELLED',
+  })
+  const controller = new AbortController()
+  const frames = Decoder.decodePath('./gifs/YESITHINKSO.gif').decodeFramesAsync(decoderOptions, {
+    signal: controller.signal,
+  })
+  controller.abort()
+  await t.throwsAsync(frames, { code: 'ERR_GIF_CANCELLED' })
+
+  // The abort listener is removed once the promise settles, whether it resolves or rejects
+  const unused = new AbortController()
+  await Decoder.decodePath('./gifs/sample_1.gif').decodeFramesAsync(decoderOptions, { signal: unused.signal })
+  await Decoder.decodeBufferAsync(readFileSync('./gifs/sample_1.gif'), undefined, { signal: unused.signal })
+  await t.throwsAsync(Decoder.decodePathAsync('./gifs/missing.gif', undefined, { signal: unused.signal }), {
+    code: 'ERR_GIF_IO',
+  })
+  t.is(getEventListeners(unused.signal, 'abort').length, 0)
+})
+
+test('Composited frames match the golden reference images', (t) => {
+  // SHA-256 of every RGBA frame, regenerated with golden.cjs
+  const golden = JSON.parse(readFileSync('./__test__/golden.json', 'utf8'))
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  for (const file of readdirSync('./gifs')) {
+    if (golden[file] === undefined) {
+      t.throws(() => Decoder.decodePath(`./gifs/${file}`))
+      continue
+    }
+    const frames = Decoder.decodePath(`./gifs/${file}`).decodeFrames(decoderOptions)
+    t.deepEqual(
+      frames.map((frame) => createHash('sha256').update(frame).digest('hex')),
+      golden[file],
+      file,
+    )
+  }
+})
+
+test('Golden reference images agree with an independent compositor', (t) => {
+  // golden.json is generated by the decoder, so it is checked against reference.cjs to not only be a snapshot
+  const golden = JSON.parse(readFileSync('./__test__/golden.json', 'utf8'))
+  for (const file of Object.keys(golden)) {
+    const frames = compositeFrames(readFileSync(`./gifs/${file}`))
+    t.deepEqual(
+      frames.map((frame) => createHash('sha256').update(frame).digest('hex')),
+      golden[file],
+      file,
+    )
+  }
+})
+
+test('disposal.gif: Disposal methods follow GIF89a', (t) => {
+  const gif = Decoder.decodePath('./gifs/disposal.gif')
+  const frames = gif.decodeFrames({
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  })
+  const pixel = (frame: Buffer, x: number, y: number) => [...frame.subarray((y * 8 + x) * 4, (y * 8 + x) * 4 + 4)]
+  // Transparent pixels of the second frame show the first one
+  t.deepEqual(pixel(frames[1], 1, 1), [255, 0, 0, 255])
+  t.deepEqual(pixel(frames[1], 2, 1), [0, 255, 0, 255])
+  // Disposal method 3 restores the area of the second frame
+  t.deepEqual(pixel(frames[2], 2, 1), [255, 0, 0, 255])
+  t.deepEqual(pixel(frames[2], 3, 3), [0, 0, 255, 255])
+  // Disposal method 2 clears the area of the third frame to transparent, the last frame is clipped to the canvas
+  t.deepEqual(pixel(frames[3], 3, 3), [0, 0, 0, 0])
+  t.deepEqual(pixel(frames[3], 4, 4), [0, 0, 0, 0])
+  t.deepEqual(pixel(frames[3], 5, 4), [255, 255, 255, 255])
+  t.deepEqual(pixel(frames[3], 1, 1), [255, 0, 0, 255])
+})
+
+test('decodeFrame composites the same frames as decodeFrames in any order', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: true,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const expected = Decoder.decodePath('./gifs/disposal.gif').decodeFrames(decoderOptions)
+  const gif = Decoder.decodePath('./gifs/disposal.gif')
+  for (const index of [2, 3, 1, 0]) {
+    t.deepEqual([...gif.decodeFrame(index, decoderOptions)], [...expected[index]])
+  }
+  const uncached = Decoder.decodePath('./gifs/disposal.gif')
+  for (const index of [3, 2, 1, 0]) {
+    t.deepEqual([...uncached.decodeFrame(index, { ...decoderOptions, storeCache: false })], [...expected[index]])
+  }
+})
+
+test('disposal.gif: Background fills the canvas and the areas disposal method 2 clears', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const pixel = (frame: Buffer, x: number, y: number) => [...frame.subarray((y * 8 + x) * 4, (y * 8 + x) * 4 + 4)]
+  const gif = Decoder.decodePath('./gifs/disposal.gif')
+  t.is(gif.lsd.backgroundColorIndex, 0)
+
+  const transparent = gif.decodeFrames({ ...decoderOptions, background: { kind: 'transparent' as const } })
+  t.deepEqual(pixel(transparent[3], 3, 3), [0, 0, 0, 0])
+
+  const logicalScreen = gif.decodeFrames({ ...decoderOptions, background: { kind: 'logicalScreen' as const } })
+  t.deepEqual(pixel(logicalScreen[3], 3, 3), [255, 0, 0, 255])
+  t.deepEqual(pixel(logicalScreen[3], 4, 4), [255, 0, 0, 255])
+  t.deepEqual(pixel(logicalScreen[3], 5, 4), [255, 255, 255, 255])
+
+  const color: [number, number, number, number] = [0, 0, 0, 128]
+  const custom = { ...decoderOptions, background: { kind: 'custom' as const, color } }
+  const frames = gif.decodeFrames(custom)
+  t.deepEqual(pixel(frames[3], 3, 3), [0, 0, 0, 128])
+  t.deepEqual([...Decoder.decodePath('./gifs/disposal.gif').decodeFrame(3, custom)], [...frames[3]])
+
+  const independent = gif.decodeFrames({ ...custom, disableDisposalMethods: true })
+  t.deepEqual(pixel(independent[1], 0, 0), [0, 0, 0, 128])
+  t.deepEqual(pixel(independent[1], 1, 1), [0, 0, 0, 128])
+  t.deepEqual(pixel(independent[1], 2, 1), [0, 255, 0, 255])
+
+  t.throws(() => gif.decodeFrames({ ...decoderOptions, background: { kind: 'custom' } }))
+  // @ts-expect-error
+  t.throws(() => gif.decodeFrames({ ...decoderOptions, background: { kind: 'white' } }))
+})
+
+test('Keyframes are frames composited without the previous ones', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  t.deepEqual(Decoder.decodePath('./gifs/disposal.gif').keyframes(decoderOptions), [0])
+  t.deepEqual(Decoder.decodePath('./gifs/YESITHINKSO.gif').keyframes(decoderOptions), [0, 1, 2, 3, 4, 5, 6, 7])
+  t.deepEqual(Decoder.decodePath('./gifs/forsenParty.gif').keyframes(decoderOptions).slice(0, 5), [0, 3, 5, 8, 10])
+  t.deepEqual(Decoder.decodePath('./gifs/disposal.gif').keyframes({ ...decoderOptions, rawDecode: true }), [0, 1, 2, 3])
+})
+
+test('decodeFrame seeks from the closest keyframe or checkpoint', (t) => {
+  const expected = Decoder.decodePath('./gifs/YESITHINKSO.gif').decodeFrames({
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  })
+  const gif = Decoder.decodePath('./gifs/YESITHINKSO.gif', { lazy: true })
+  // Room for 16 canvases, one every 11 frames
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+    checkpointBudget: gif.lsd.width * gif.lsd.height * 4 * 16,
+  }
+
+  t.deepEqual([...gif.decodeFrame(161, decoderOptions)], [...expected[161]])
+
+  const fresh = Decoder.decodePath('./gifs/YESITHINKSO.gif', { lazy: true })
+  t.deepEqual([...fresh.decodeFrame(161, { ...decoderOptions, freeIndexStreams: true })], [...expected[161]])
+  t.deepEqual([...fresh.decodeFrame(150, decoderOptions)], [...expected[150]])
+
+  for (const index of [100, 37, 160, 8, 0, 121]) {
+    t.deepEqual([...fresh.decodeFrame(index, decoderOptions)], [...expected[index]])
+  }
+  t.true(fresh.frames.every((_, index) => !fresh.cachedFrame(index)))
+})
+
+test('Frame cache evicts the least recently used frames past its budget', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: true,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const expected = Decoder.decodePath('./gifs/forsenParty.gif').decodeFrames({ ...decoderOptions, storeCache: false })
+  const gif = Decoder.decodePath('./gifs/forsenParty.gif')
+  const canvasSize = gif.lsd.width * gif.lsd.height * 4
+  const bounded = { ...decoderOptions, cacheBudget: canvasSize * 2 }
+
+  gif.decodeFrame(0, bounded)
+  gif.decodeFrame(3, bounded)
+  t.deepEqual([...gif.decodeFrame(0, bounded)], [...expected[0]])
+  gif.decodeFrame(5, bounded)
+  t.deepEqual(
+    gif.frames.flatMap((_, index) => (gif.cachedFrame(index) ? [index] : [])),
+    [0, 5],
+  )
+  const stats = gif.cacheStats()
+  t.is(stats.frames, 2)
+  t.is(stats.bytes, canvasSize * 2)
+  t.is(stats.hits, 1)
+  t.is(stats.misses, 3)
+  t.is(stats.evictions, 1)
+
+  const frames = gif.decodeFrames(bounded)
+  t.deepEqual(
+    frames.map((frame) => [...frame]),
+    expected.map((frame) => [...frame]),
+  )
+  t.deepEqual(
+    gif.frames.flatMap((_, index) => (gif.cachedFrame(index) ? [index] : [])),
+    [58, 59],
+  )
+  t.true(gif.cacheStats().bytes <= canvasSize * 2)
+})
+
+test('Cached frames are dropped when decoding with options compositing differently', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: true,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const gif = Decoder.decodePath('./gifs/disposal.gif')
+  gif.decodeFrames(decoderOptions)
+  for (const options of [
+    { ...decoderOptions, background: { kind: 'custom', color: [255, 255, 255, 255] } as const },
+    { ...decoderOptions, rawDecode: true },
+    decoderOptions,
+  ]) {
+    const expected = Decoder.decodePath('./gifs/disposal.gif').decodeFrame(3, options)
+    t.deepEqual([...gif.decodeFrame(3, options)], [...expected])
+  }
+  t.is(gif.cacheStats().hits, 0)
+})
+
+test('clearCache drops the cached frames and checkpoints', (t) => {
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: true,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  const gif = Decoder.decodePath('./gifs/YESITHINKSO.gif')
+  const expected = gif.decodeFrame(161, decoderOptions)
+  const stats = gif.cacheStats()
+  t.is(stats.frames, 155)
+  t.true(stats.checkpoints > 0)
+  t.true(stats.checkpointBytes > 0)
+
+  gif.clearCache()
+  t.deepEqual(
+    { ...gif.cacheStats(), hits: 0, misses: 0, evictions: 0 },
+    { frames: 0, bytes: 0, checkpoints: 0, checkpointBytes: 0, hits: 0, misses: 0, evictions: 0 },
+  )
+  t.true(gif.frames.every((_, index) => !gif.cachedFrame(index) && !gif.previousPixels(index)))
+  t.deepEqual([...gif.decodeFrame(161, decoderOptions)], [...expected])
+})
+
+test('disposal.gif: Deltas are the area of each frame with its disposal', (t) => {
+  const deltas = Decoder.decodePath('./gifs/disposal.gif').decodeDeltas({
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  })
+  t.deepEqual(
+    deltas.map(({ left, top, width, height, disposal }) => ({ left, top, width, height, disposal })),
+    [
+      { left: 0, top: 0, width: 8, height: 8, disposal: 'keep' },
+      { left: 1, top: 1, width: 4, height: 4, disposal: 'previous' },
+      { left: 3, top: 3, width: 4, height: 4, disposal: 'background' },
+      { left: 4, top: 4, width: 4, height: 4, disposal: 'keep' },
+    ],
+  )
+  t.deepEqual([...deltas[1].pixels.subarray(0, 8)], [0, 0, 0, 0, 0, 255, 0, 255])
+  t.deepEqual([...deltas[3].pixels.subarray(0, 8)], [0, 0, 0, 0, 255, 255, 255, 255])
+  t.is(deltas[3].pixels.length, 4 * 4 * 4)
+})
+
+test('Compositing the deltas gives the golden reference images', (t) => {
+  const golden = JSON.parse(readFileSync('./__test__/golden.json', 'utf8'))
+  const decoderOptions = {
+    implementDisposalPrevious: true,
+    storeCache: false,
+    disableDisposalMethods: false,
+    rawDecode: false,
+  }
+  for (const file of Object.keys(golden)) {
+    const gif = Decoder.decodePath(`./gifs/${file}`)
+    const width = gif.lsd.width
+    let canvas = Buffer.alloc(width * gif.lsd.height * 4)
+    const hashes = []
+    for (const delta of gif.decodeDeltas(decoderOptions)) {
+      const previous = Buffer.from(canvas)
+      for (let y = 0; y < delta.height; y++) {
+        for (let x = 0; x < delta.width; x++) {
+          const source = (y * delta.width + x) * 4
+          if (delta.pixels[source + 3] !== 0) {
+            delta.pixels.copy(canvas, ((delta.top + y) * width + delta.left + x) * 4, source, source + 4)
+          }
+        }
+      }
+      hashes.push(createHash('sha256').update(canvas).digest('hex'))
+      if (delta.disposal === 'previous') {
+        canvas = previous
+      } else if (delta.disposal === 'background') {
+        for (let y = 0; y < delta.height; y++) {
+          const start = ((delta.top + y) * width + delta.left) * 4
+          canvas.fill(0, start, start + delta.width * 4)
+        }
+      }
+    }
+    t.deepEqual(hashes, golden[file], file)
+  }
+})
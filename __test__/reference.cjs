@@ -0,0 +1,154 @@
+// Minimal GIF89a compositor written from the specification, sharing no code with the decoder,
+// golden.json is checked against it so the golden frames are not only a snapshot of the decoder
+// Plain text is not rendered and disposal method 3 always restores the previous canvas
+
+function readSubBlocks(contents, offset) {
+  const chunks = []
+  while (contents[offset] !== 0) {
+    chunks.push(contents.subarray(offset + 1, offset + 1 + contents[offset]))
+    offset += 1 + contents[offset]
+  }
+  return [Buffer.concat(chunks), offset + 1]
+}
+
+function readColorTable(contents, offset, packedField) {
+  const colors = []
+  for (let i = 0; i < 2 << (packedField & 7); i++) {
+    colors.push(contents.subarray(offset + i * 3, offset + i * 3 + 3))
+  }
+  return colors
+}
+
+function decompress(data, minimumCodeSize, pixels) {
+  const clearCode = 1 << minimumCodeSize
+  const endCode = clearCode + 1
+  const output = []
+  let table, codeSize, previous
+  const reset = () => {
+    table = Array.from({ length: clearCode + 2 }, (_, code) => (code < clearCode ? [code] : []))
+    codeSize = minimumCodeSize + 1
+    previous = null
+  }
+  reset()
+  let bits = 0
+  let bitCount = 0
+  for (let i = 0; output.length < pixels; ) {
+    while (bitCount < codeSize) {
+      if (i >= data.length) return output
+      bits |= data[i++] << bitCount
+      bitCount += 8
+    }
+    const code = bits & ((1 << codeSize) - 1)
+    bits >>= codeSize
+    bitCount -= codeSize
+    if (code === clearCode) {
+      reset()
+      continue
+    }
+    if (code === endCode) break
+    let entry
+    if (previous === null) {
+      if (code >= table.length) break
+      entry = table[code]
+    } else {
+      if (code < table.length) entry = table[code]
+      else if (code === table.length) entry = [...previous, previous[0]]
+      else break
+      if (table.length < 4096) table.push([...previous, entry[0]])
+    }
+    output.push(...entry)
+    previous = entry
+    if (table.length === 1 << codeSize && codeSize < 12) codeSize++
+  }
+  return output.slice(0, pixels)
+}
+
+function deinterlace(indexes, width, height) {
+  const rows = []
+  for (const [start, step] of [
+    [0, 8],
+    [4, 8],
+    [2, 4],
+    [1, 2],
+  ]) {
+    for (let y = start; y < height; y += step) rows.push(y)
+  }
+  const output = new Array(width * height)
+  rows.forEach((y, row) => {
+    for (let x = 0; x < width; x++) output[y * width + x] = indexes[row * width + x]
+  })
+  return output
+}
+
+// Every composited RGBA frame, drawn onto a transparent canvas
+function compositeFrames(contents) {
+  const width = contents.readUInt16LE(6)
+  const height = contents.readUInt16LE(8)
+  let offset = 13
+  let globalColorTable = []
+  if (contents[10] & 0x80) {
+    globalColorTable = readColorTable(contents, offset, contents[10])
+    offset += globalColorTable.length * 3
+  }
+
+  const frames = []
+  let gce = null
+  while (offset < contents.length && contents[offset] !== 0x3b) {
+    const introducer = contents[offset++]
+    if (introducer === 0x21) {
+      const label = contents[offset++]
+      const [data, next] = readSubBlocks(contents, offset)
+      if (label === 0xf9) {
+        gce = { disposal: (data[0] >> 2) & 7, transparentIndex: data[0] & 1 ? data[3] : null }
+      } else if (label === 0x01) {
+        const rect = [0, 2, 4, 6].map((field) => data.readUInt16LE(field))
+        frames.push({ rect, gce, indexes: [] })
+        gce = null
+      }
+      offset = next
+    } else if (introducer === 0x2c) {
+      const rect = [0, 2, 4, 6].map((field) => contents.readUInt16LE(offset + field))
+      const packedField = contents[offset + 8]
+      offset += 9
+      let colorTable = globalColorTable
+      if (packedField & 0x80) {
+        colorTable = readColorTable(contents, offset, packedField)
+        offset += colorTable.length * 3
+      }
+      const minimumCodeSize = contents[offset++]
+      const [data, next] = readSubBlocks(contents, offset)
+      offset = next
+      const pixels = rect[2] * rect[3]
+      let indexes = decompress(data, minimumCodeSize, pixels)
+      if (packedField & 0x40 && indexes.length === pixels) indexes = deinterlace(indexes, rect[2], rect[3])
+      frames.push({ rect, gce, colorTable, indexes })
+      gce = null
+    } else {
+      break
+    }
+  }
+
+  let canvas = Buffer.alloc(width * height * 4)
+  return frames.map(({ rect: [left, top, frameWidth, frameHeight], gce, colorTable, indexes }) => {
+    const disposal = gce ? gce.disposal : 0
+    const previous = Buffer.from(canvas)
+    indexes.forEach((index, i) => {
+      const x = left + (i % frameWidth)
+      const y = top + Math.floor(i / frameWidth)
+      if (x >= width || y >= height || index === gce?.transparentIndex || index >= colorTable.length) return
+      colorTable[index].copy(canvas, (y * width + x) * 4)
+      canvas[(y * width + x) * 4 + 3] = 255
+    })
+    const frame = Buffer.from(canvas)
+    if (disposal === 2) {
+      for (let y = top; y < Math.min(top + frameHeight, height); y++) {
+        canvas.fill(0, (y * width + left) * 4, (y * width + Math.min(left + frameWidth, width)) * 4)
+      }
+    } else if (disposal === 3) {
+      canvas = previous
+    }
+    return frame
+  })
+}
+
+module.exports = { compositeFrames }
@@ -3,6 +3,34 @@
 
 /* auto-generated by NAPI-RS */
 
+/** What the caches of a Gif hold, returned by gif.cacheStats */
+export interface CacheStats {
+  /** Frames with a cachedFrame or previousPixels */
+  frames: number
+  /** Bytes of every cachedFrame and previousPixels, bounded by DecoderOptions.cacheBudget */
+  bytes: number
+  /** Composited canvases decodeFrame seeks from */
+  checkpoints: number
+  /** Bytes of the checkpoints, bounded by DecoderOptions.checkpointBudget */
+  checkpointBytes: number
+  /** decodeFrame calls answered with a cached frame */
+  hits: number
+  /** decodeFrame calls that had to composite the frame */
+  misses: number
+  /** Frames whose cache was dropped to stay within DecoderOptions.cacheBudget */
+  evictions: number
+}
+/** How far a decode got, reported after every frame */
+export interface Progress {
+  /** Frames parsed or composited so far */
+  frames: number
+  /** Number of frames to composite, absent while parsing as it is not known yet */
+  frameCount?: number
+  /** Bytes of the file parsed, or of compressed image data decompressed when compositing, so far */
+  bytes: number
+  /** Size of the file, or of the compressed image data of every frame when compositing */
+  totalBytes: number
+}
 export interface DecoderOptions {
   /** Whether to implement the disposal method of the previous frame, default is `true` */
   implementDisposalPrevious: boolean
@@ -12,6 +40,40 @@ export interface DecoderOptions {
   disableDisposalMethods: boolean
   /** Whether to return the raw decoded frame, also disables the use of any disposal methods, default is `false` */
   rawDecode: boolean
+  /** Whether to draw the text of Plain Text Extensions onto the frames using the built-in 8x8 font, default is `false` */
+  renderPlainText?: boolean
+  /** Whether to convert the colour tables into sRGB using the embedded ICC profile before compositing, default is `false` */
+  convertToSrgb?: boolean
+  /** Whether to free the index streams once the frames are decoded, only applies to Gifs parsed with ParseOptions.lazy as they can be decompressed again, default is `false` */
+  freeIndexStreams?: boolean
+  /** Colour of the canvas before the first frame and of the areas disposal method 2 clears, default is transparent */
+  background?: Background
+  /** Bytes of composited canvases decodeFrame keeps to seek from instead of compositing every previous frame, default is `16777216` */
+  checkpointBudget?: number
+  /** Bytes of cachedFrame and previousPixels kept with storeCache, the least recently used frames are evicted first, default is no limit */
+  cacheBudget?: number
+}
+export interface ParseOptions {
+  /** Whether to reject files with a version other than `87a` | `89a` or with unknown block introducers, browsers still display them so disable this to read them anyway, default is `true` */
+  strict?: boolean
+  /** Whether to only record where the compressed image data is and decompress the index streams when the frames are decoded, default is `false` */
+  lazy?: boolean
+  /** Whether to decompress the index streams of the frames in parallel on a thread pool once the file is parsed, ignored with lazy, default is `false` */
+  parallel?: boolean
+  /** Whether to memory map the file in decodePath | probePath instead of reading it into memory, falls back to reading when the file can not be mapped, the file must not be modified while it is mapped, default is `false` */
+  memoryMap?: boolean
+  /** Limits on the dimensions, frame count and decompressed size, failing with ERR_GIF_LIMITS_EXCEEDED, default is no limits */
+  limits?: DecoderLimits
+}
+/** Summary of a Gif, returned by Decoder.probePath | Decoder.probeBuffer */
+export interface GifInfo {
+  width: number
+  height: number
+  frameCount: number
+  /** Sum of the frame delays in milliseconds, saturating at 4294967295 */
+  totalDuration: number
+  /** Number of times the animation repeats, `0` means loop forever */
+  loopCount?: number
 }
 export interface LogicalScreenDescriptor {
   width: number
@@ -43,25 +105,174 @@ export interface Color {
   green: number
   blue: number
 }
-export declare class Gif {
-  version: string
-  lsd: LogicalScreenDescriptor
-  globalTable: Array<Color>
-  frames: Array<Frame>
-  decodeFrames(decoderOptions: DecoderOptions): Array<Buffer>
-  decodeFrame(frameIndex: number, decoderOptions: DecoderOptions): Buffer
+export interface PlainTextExtension {
+  textGridLeft: number
+  textGridTop: number
+  textGridWidth: number
+  textGridHeight: number
+  characterCellWidth: number
+  characterCellHeight: number
+  textForegroundColorIndex: number
+  textBackgroundColorIndex: number
+  text: string
+}
+export interface ApplicationExtension {
+  /** Byte offset of the extension introducer in the file */
+  offset: number
+  identifier: string
+  authenticationCode: string
+  /** Payload of the data sub-blocks, concatenated */
+  data: Array<number>
+  /** XML of the XMP packet, only present for `XMP DataXMP` extensions */
+  xmp?: string
+}
+export interface UnknownExtension {
+  /** Byte offset of the extension introducer in the file */
+  offset: number
+  label: number
+  /** Payload of the data sub-blocks, concatenated */
+  data: Array<number>
+}
+export interface CommentExtension {
+  /** Index of the frame the comment precedes, equal to the frame count when it comes after the last frame */
+  frameIndex: number
+  data: Array<number>
+  /** `data` decoded as UTF-8, falling back to Latin-1 when it is not valid UTF-8 */
+  text: string
+}
+/** Bounds checked while parsing, before anything is allocated for the image data, every limit is unset by default */
+export interface DecoderLimits {
+  /** Maximum width of the logical screen and of every frame */
+  maxWidth?: number
+  /** Maximum height of the logical screen and of every frame */
+  maxHeight?: number
+  /** Maximum number of pixels (width * height) of the logical screen and of every frame */
+  maxPixels?: number
+  /** Maximum number of frames, plain text frames included */
+  maxFrames?: number
+  /** Maximum number of bytes of the decompressed index streams and of the RGBA frames decodeFrames composites, one per frame */
+  maxDecodedBytes?: number
+  /** Maximum number of pixels decompressed from each byte of LZW data in a frame */
+  maxCompressionRatio?: number
+}
+/** DecoderOptions.background, `{ kind: 'custom', color: [red, green, blue, alpha] }` for a custom colour */
+export interface Background {
+  /** `transparent` | `logicalScreen` | `custom`, logicalScreen is the opaque colour at lsd.backgroundColorIndex of the global colour table */
+  kind: 'transparent' | 'logicalScreen' | 'custom'
+  /** RGBA of `custom`, every channel from 0 to 255 */
+  color?: [number, number, number, number]
 }
-export declare class Frame {
-  gcd: GraphicsControlExtension
+/** Progress callback and cancellation of the async methods */
+export interface DecodeControl {
+  /** Called on the event loop after every frame, the last calls may arrive after the promise settles */
+  onProgress?: (progress: Progress) => void
+  /** Rejects with ERR_GIF_CANCELLED once aborted, checked between frames and periodically while decompressing */
+  signal?: AbortSignal
+}
+/** Result of FrameIterator.next, following the iterator protocol */
+export interface FrameIteratorResult {
+  done: boolean
+  value?: Buffer
+}
+export interface Frame {
+  /** Absent when the frame is not preceded by a Graphic Control Extension, e.g. in GIF87a files */
+  gcd?: GraphicsControlExtension
   im: ImageDescriptor
   colorTable: Array<Color>
+  /** Empty when parsed with ParseOptions.lazy, gif.indexStream decompresses it */
   indexStream: Array<number>
-  /** Generated when decoding the frame, used to properly implement disposal method 0 | 1, can be disabled using DecoderOptions.storeCache */
-  cachedFrame?: Buffer
-  /** Generated when decoding the frame if any disposal method is 3 present in the Gif, used when decoding individual frames, can be disabled using DecoderOptions.implementDisposalPrevious */
-  previousPixels?: Buffer
+  /** Present when the frame is drawn by a Plain Text Extension instead of an image */
+  plainText?: PlainTextExtension
+}
+/** A frame as the area it draws onto the canvas, returned by gif.decodeDeltas */
+export interface FrameDelta {
+  /** Position of the area on the canvas, clipped to the logical screen */
+  left: number
+  top: number
+  width: number
+  height: number
+  /** RGBA of the area, transparent black where the frame leaves the canvas as it is */
+  pixels: Buffer
+  /** What to do with the area once the frame was displayed, `keep` it, clear it to the `background` or restore it to what it was before the frame, the `previous` pixels */
+  disposal: 'keep' | 'background' | 'previous'
+}
+/** Something the StreamingDecoder finished reading, only the fields of its kind are present */
+export interface StreamEvent {
+  /** `header` | `applicationExtension` | `comment` | `unknownExtension` | `frame` | `end` */
+  kind: string
+  version?: string
+  lsd?: LogicalScreenDescriptor
+  globalTable?: Array<Color>
+  applicationExtension?: ApplicationExtension
+  comment?: CommentExtension
+  unknownExtension?: UnknownExtension
+  frameIndex?: number
+  frame?: Frame
+}
+export declare class Gif {
+  get version(): string
+  get lsd(): LogicalScreenDescriptor
+  get globalTable(): Array<Color>
+  get frames(): Array<Frame>
+  /** Number of times the animation repeats, taken from the NETSCAPE2.0 / ANIMEXTS1.0 extension, `0` means loop forever */
+  get loopCount(): number | null
+  /** Suggested buffer size in bytes, taken from the NETSCAPE2.0 / ANIMEXTS1.0 extension */
+  get bufferSize(): number | null
+  get comments(): Array<CommentExtension>
+  get applicationExtensions(): Array<ApplicationExtension>
+  get unknownExtensions(): Array<UnknownExtension>
+  /** Graphic Control Extensions that were not followed by an image or plain text */
+  get orphanedGcds(): Array<GraphicsControlExtension>
+  /** ICC colour profile, taken from the ICCRGBG1 extension */
+  get iccProfile(): Array<number> | null
+  decodeFrames(decoderOptions: DecoderOptions): Array<Buffer>
+  /**
+  * Every frame as the area it draws, where to draw it and how to dispose of it, for compositing the frames elsewhere,
+  * drawing `pixels` where they are opaque onto the canvas then applying `disposal` composites the same frames as decodeFrames
+  */
+  decodeDeltas(decoderOptions: DecoderOptions): Array<FrameDelta>
+  decodeDelta(frameIndex: number, decoderOptions: DecoderOptions): FrameDelta
+  /** Index stream of the frame, decompressed first when parsed with ParseOptions.lazy */
+  indexStream(frameIndex: number): Buffer
+  /** Same as decodeFrames, on the libuv threadpool */
+  decodeFramesAsync(decoderOptions: DecoderOptions, control?: DecodeControl | undefined | null): Promise<Array<Buffer>>
+  /** Same as decodeFrame, on the libuv threadpool */
+  decodeFrameAsync(frameIndex: number, decoderOptions: DecoderOptions): Promise<Buffer>
+  decodeFrame(frameIndex: number, decoderOptions: DecoderOptions): Buffer
+  /** Copy of the frame cached when decoding it, decodeFrame resumes compositing from it instead of the first frame, can be disabled using DecoderOptions.storeCache and bounded with DecoderOptions.cacheBudget */
+  cachedFrame(frameIndex: number): Buffer | null
+  /** Copy of the canvas the frame was drawn onto and restores to, cached with the frame when it uses disposal method 3 */
+  previousPixels(frameIndex: number): Buffer | null
+  /** Drops every cached frame and checkpoint, the next decodes composite the frames again */
+  clearCache(): void
+  /** Counted once the calls finish, a running async decode is only counted once its promise settles */
+  cacheStats(): CacheStats
+  /** Frames composited the same onto an empty canvas as onto the previous frames, decodeFrame never composites frames before the closest one */
+  keyframes(decoderOptions: DecoderOptions): Array<number>
+  /** Composites the frames one at a time, only keeping the canvas the next frame is drawn onto, frames are never cached regardless of storeCache */
+  iterFrames(decoderOptions: DecoderOptions): FrameIterator & IterableIterator<Buffer>
+}
+/** Yields the composited frames of a Gif, created by gif.iterFrames */
+export declare class FrameIterator {
+  next(): IteratorResult<Buffer, undefined>
 }
 export declare class Decoder {
-  static decodePath(filePath: string): Gif
-  static decodeBuffer(buffer: Buffer): Gif
+  static decodePath(filePath: string, parseOptions?: ParseOptions | undefined | null): Gif
+  /** Gifs parsed with ParseOptions.lazy read the buffer in place, so it must not be modified while the Gif is used */
+  static decodeBuffer(buffer: Buffer, parseOptions?: ParseOptions | undefined | null): Gif
+  /** Same as decodePath, on the libuv threadpool */
+  static decodePathAsync(filePath: string, parseOptions?: ParseOptions | undefined | null, control?: DecodeControl | undefined | null): Promise<Gif>
+  /** Same as decodeBuffer, on the libuv threadpool, the buffer is read in place so it must not be modified until the promise settles */
+  static decodeBufferAsync(buffer: Buffer, parseOptions?: ParseOptions | undefined | null, control?: DecodeControl | undefined | null): Promise<Gif>
+  /** Reads the dimensions, frame count, duration and loop count without decompressing the image data */
+  static probePath(filePath: string, parseOptions?: ParseOptions | undefined | null): GifInfo
+  /** Reads the dimensions, frame count, duration and loop count without decompressing the image data */
+  static probeBuffer(buffer: Buffer, parseOptions?: ParseOptions | undefined | null): GifInfo
+}
+export declare class StreamingDecoder {
+  constructor(parseOptions?: ParseOptions | undefined | null)
+  /** Appends the chunk and returns the events of every block that is now complete */
+  push(chunk: Buffer): Array<StreamEvent>
+  /** Returns the Gif and resets the decoder, throws when the data is incomplete */
+  finish(): Gif
 }
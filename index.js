@@ -284,8 +284,9 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { Gif, Frame, Decoder } = nativeBinding
+const { Gif, FrameIterator, Decoder, StreamingDecoder } = nativeBinding
 
 module.exports.Gif = Gif
-module.exports.Frame = Frame
+module.exports.FrameIterator = FrameIterator
 module.exports.Decoder = Decoder
+module.exports.StreamingDecoder = StreamingDecoder
@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::compositor;
+use crate::gif::{DecoderOptions, Frame};
+
+/// What the caches of a Gif hold, returned by gif.cacheStats
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct CacheStats {
+  /// Frames with a cachedFrame or previousPixels
+  pub frames: u32,
+  /// Bytes of every cachedFrame and previousPixels, bounded by DecoderOptions.cacheBudget
+  pub bytes: u32,
+  /// Composited canvases decodeFrame seeks from
+  pub checkpoints: u32,
+  /// Bytes of the checkpoints, bounded by DecoderOptions.checkpointBudget
+  pub checkpoint_bytes: u32,
+  /// decodeFrame calls answered with a cached frame
+  pub hits: u32,
+  /// decodeFrame calls that had to composite the frame
+  pub misses: u32,
+  /// Frames whose cache was dropped to stay within DecoderOptions.cacheBudget
+  pub evictions: u32,
+}
+
+/// Least recently used order of the cached frames of a Gif
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FrameCache {
+  /// Options the cached frames were composited with, absent while nothing is cached
+  decoder_options: Option<DecoderOptions>,
+  /// Incremented every time a frame is used
+  tick: u64,
+  /// Cached frames by the tick they were last used at, the first one is evicted first
+  frames_by_tick: BTreeMap<u64, usize>,
+  ticks_by_frame: HashMap<usize, u64>,
+  /// Bytes of the cachedFrame and previousPixels stored through the cache
+  bytes: usize,
+  pub(crate) hits: u32,
+  pub(crate) misses: u32,
+  pub(crate) evictions: u32,
+}
+
+impl FrameCache {
+  /// Drops every cached frame when they were composited differently than with these options
+  pub(crate) fn validate(&mut self, frames: &mut [Frame], decoder_options: &DecoderOptions) {
+    if let Some(cached_with) = &self.decoder_options {
+      if !compositor::composites_alike(cached_with, decoder_options) {
+        self.clear(frames);
+      }
+    }
+  }
+
+  /// Caches the frame, evicting the least recently used frames until the cache fits in DecoderOptions.cacheBudget,
+  /// nothing is cached when the frame alone does not fit
+  pub(crate) fn store(
+    &mut self,
+    frames: &mut [Frame],
+    frame_index: usize,
+    cached_frame: Vec<u8>,
+    previous_pixels: Option<Vec<u8>>,
+    decoder_options: &DecoderOptions,
+  ) {
+    self.validate(frames, decoder_options);
+    self.remove(frames, frame_index);
+    let size = cached_frame.len() + previous_pixels.as_ref().map_or(0, Vec::len);
+    let budget = decoder_options.cache_budget;
+    if let Some(budget) = budget {
+      if size > budget as usize {
+        return;
+      }
+    }
+
+    self.decoder_options = Some(decoder_options.clone());
+    let frame = &mut frames[frame_index];
+    frame.cached_frame = Some(cached_frame);
+    frame.previous_pixels = previous_pixels;
+    self.bytes += size;
+    self.touch(frame_index);
+
+    if let Some(budget) = budget {
+      while self.bytes > budget as usize {
+        let least_recently_used = match self.frames_by_tick.first_key_value() {
+          Some((_, least_recently_used)) => *least_recently_used,
+          None => break,
+        };
+        self.remove(frames, least_recently_used);
+        self.evictions += 1;
+      }
+    }
+  }
+
+  /// Marks the frame as the most recently used one
+  pub(crate) fn touch(&mut self, frame_index: usize) {
+    if let Some(tick) = self.ticks_by_frame.insert(frame_index, self.tick) {
+      self.frames_by_tick.remove(&tick);
+    }
+    self.frames_by_tick.insert(self.tick, frame_index);
+    self.tick += 1;
+  }
+
+  pub(crate) fn clear(&mut self, frames: &mut [Frame]) {
+    for frame in frames.iter_mut() {
+      frame.cached_frame = None;
+      frame.previous_pixels = None;
+    }
+    self.frames_by_tick.clear();
+    self.ticks_by_frame.clear();
+    self.bytes = 0;
+    self.decoder_options = None;
+  }
+
+  fn remove(&mut self, frames: &mut [Frame], frame_index: usize) {
+    if let Some(tick) = self.ticks_by_frame.remove(&frame_index) {
+      self.frames_by_tick.remove(&tick);
+    }
+    let frame = &mut frames[frame_index];
+    let size = frame.cached_frame.take().map_or(0, |buffer| buffer.len())
+      + frame
+        .previous_pixels
+        .take()
+        .map_or(0, |buffer| buffer.len());
+    self.bytes = self.bytes.saturating_sub(size);
+  }
+}
@@ -0,0 +1,279 @@
+use crate::control::DecodeControl;
+use crate::error::Result;
+use crate::font;
+use crate::gif::{DecoderOptions, Frame, Gif};
+
+pub(crate) const DISPOSAL_UNSPECIFIED: u32 = 0;
+pub(crate) const DISPOSAL_BACKGROUND: u32 = 2;
+pub(crate) const DISPOSAL_PREVIOUS: u32 = 3;
+
+/// Composites the frames of a Gif in order following the GIF89a disposal methods: the area of the previous frame
+/// is cleared to the background or restored, then the frame is drawn on top of what is left, skipping its transparent pixels
+pub(crate) struct Compositor {
+  decoder_options: DecoderOptions,
+  /// Index of the next frame to composite
+  frame_index: usize,
+  /// What the next frame is drawn onto, the previous frame once its disposal method was applied, empty when absent
+  canvas: Option<Vec<u8>>,
+  /// Whether to keep checkpoints of the canvas in the seek index of the Gif
+  record_checkpoints: bool,
+}
+
+impl Compositor {
+  pub(crate) fn new(decoder_options: &DecoderOptions) -> Compositor {
+    Compositor {
+      decoder_options: decoder_options.clone(),
+      frame_index: 0,
+      canvas: None,
+      record_checkpoints: false,
+    }
+  }
+
+  /// Compositor storing nothing in the frames regardless of DecoderOptions.storeCache, used by the frame iterators
+  pub(crate) fn without_cache(decoder_options: &DecoderOptions) -> Compositor {
+    // Caching every frame would keep the whole animation in memory
+    Compositor::new(&DecoderOptions {
+      store_cache: false,
+      ..decoder_options.clone()
+    })
+  }
+
+  /// Compositor starting from the closest keyframe, checkpoint or cached frame before `frame_index`,
+  /// taking checkpoints along the way within DecoderOptions.checkpointBudget
+  pub(crate) fn seek(
+    gif: &mut Gif,
+    decoder_options: &DecoderOptions,
+    frame_index: usize,
+  ) -> Compositor {
+    let mut compositor = Compositor::new(decoder_options);
+    if compositor.independent_frames() {
+      compositor.frame_index = frame_index;
+      return compositor;
+    }
+    compositor.record_checkpoints = true;
+
+    let (start, checkpoint) = gif.seek_index(decoder_options).start(frame_index);
+    compositor.frame_index = start;
+    compositor.canvas = checkpoint.cloned();
+    for i in (start..frame_index).rev() {
+      let frame = &gif.frames[i];
+      let cached_frame = match &frame.cached_frame {
+        Some(cached_frame) => cached_frame,
+        None => continue,
+      };
+      let canvas = match disposal_method(frame, decoder_options) {
+        DISPOSAL_PREVIOUS => match &frame.previous_pixels {
+          Some(previous_pixels) => previous_pixels.clone(),
+          None => continue,
+        },
+        DISPOSAL_BACKGROUND => {
+          let mut canvas = cached_frame.clone();
+          clear_rect(gif, frame, &mut canvas, decoder_options);
+          canvas
+        }
+        _ => cached_frame.clone(),
+      };
+      gif.cache.touch(i);
+      compositor.frame_index = i + 1;
+      compositor.canvas = Some(canvas);
+      break;
+    }
+    compositor
+  }
+
+  pub(crate) fn frame_index(&self) -> usize {
+    self.frame_index
+  }
+
+  pub(crate) fn remaining(&self, gif: &Gif) -> usize {
+    gif.frames.len().saturating_sub(self.frame_index)
+  }
+
+  /// Decompresses and composites the next frame, nothing is composited anymore once an error is returned
+  pub(crate) fn next(&mut self, gif: &mut Gif, control: &DecodeControl) -> Option<Result<Vec<u8>>> {
+    let frame_index = self.frame_index;
+    if frame_index >= gif.frames.len() {
+      return None;
+    }
+
+    if let Err(error) = gif.decompress_index_stream(frame_index, control) {
+      self.frame_index = gif.frames.len();
+      self.canvas = None;
+      return Some(Err(error));
+    }
+    let buffer = self.composite(gif);
+    gif.free_index_stream(frame_index, &self.decoder_options);
+    if self.record_checkpoints {
+      if let (Some(seek_index), Some(canvas)) = (&mut gif.seek_index, &self.canvas) {
+        seek_index.record(self.frame_index, canvas);
+      }
+    }
+    Some(Ok(buffer))
+  }
+
+  fn composite(&mut self, gif: &mut Gif) -> Vec<u8> {
+    let frame_index = self.frame_index;
+    self.frame_index += 1;
+
+    let mut previous_pixels = None;
+    let buffer = if self.decoder_options.raw_decode {
+      draw_raw(gif, &gif.frames[frame_index], &self.decoder_options)
+    } else if self.decoder_options.disable_disposal_methods {
+      let mut buffer = Vec::new();
+      gif.fill_with_empty_color(&mut buffer, &self.decoder_options);
+      draw(
+        gif,
+        &gif.frames[frame_index],
+        &mut buffer,
+        &self.decoder_options,
+      );
+      buffer
+    } else {
+      let frame = &gif.frames[frame_index];
+      let mut canvas = match self.canvas.take() {
+        Some(canvas) => canvas,
+        None => {
+          let mut canvas = Vec::new();
+          gif.fill_with_empty_color(&mut canvas, &self.decoder_options);
+          canvas
+        }
+      };
+      let disposal_method = disposal_method(frame, &self.decoder_options);
+      // The canvas disposal method 3 restores to once the frame was displayed
+      previous_pixels = (disposal_method == DISPOSAL_PREVIOUS).then(|| canvas.clone());
+      draw(gif, frame, &mut canvas, &self.decoder_options);
+
+      let buffer = match &previous_pixels {
+        Some(previous_pixels) => std::mem::replace(&mut canvas, previous_pixels.clone()),
+        None => {
+          let buffer = canvas.clone();
+          if disposal_method == DISPOSAL_BACKGROUND {
+            clear_rect(gif, frame, &mut canvas, &self.decoder_options);
+          }
+          buffer
+        }
+      };
+      if self.frame_index < gif.frames.len() {
+        self.canvas = Some(canvas);
+      }
+      buffer
+    };
+
+    if self.decoder_options.store_cache {
+      gif.cache.store(
+        &mut gif.frames,
+        frame_index,
+        buffer.clone(),
+        previous_pixels,
+        &self.decoder_options,
+      );
+    }
+    buffer
+  }
+
+  /// Frames are drawn onto an empty canvas instead of the previous frame
+  fn independent_frames(&self) -> bool {
+    self.decoder_options.raw_decode || self.decoder_options.disable_disposal_methods
+  }
+}
+
+/// Whether both options composite the same frames
+pub(crate) fn composites_alike(a: &DecoderOptions, b: &DecoderOptions) -> bool {
+  a.implement_disposal_previous == b.implement_disposal_previous
+    && a.raw_decode == b.raw_decode
+    && a.disable_disposal_methods == b.disable_disposal_methods
+    && a.render_plain_text == b.render_plain_text
+    && a.convert_to_srgb == b.convert_to_srgb
+    && a.background == b.background
+}
+
+/// Disposal method 3 is handled as 1 when DecoderOptions.implementDisposalPrevious is disabled
+pub(crate) fn disposal_method(frame: &Frame, decoder_options: &DecoderOptions) -> u32 {
+  match frame.disposal_method() {
+    DISPOSAL_PREVIOUS if !decoder_options.implement_disposal_previous => DISPOSAL_UNSPECIFIED,
+    disposal_method => disposal_method,
+  }
+}
+
+/// Area of the canvas covered by the frame as `(left, top, width, height)`, the text grid for plain text frames
+pub(crate) fn frame_rect(frame: &Frame) -> (u32, u32, u32, u32) {
+  match &frame.plain_text {
+    Some(plain_text) => (
+      plain_text.text_grid_left,
+      plain_text.text_grid_top,
+      plain_text.text_grid_width,
+      plain_text.text_grid_height,
+    ),
+    None => (frame.im.left, frame.im.top, frame.im.width, frame.im.height),
+  }
+}
+
+/// Draws the frame onto the canvas, pixels with the transparent index or outside of the canvas are left untouched
+fn draw(gif: &Gif, frame: &Frame, canvas: &mut [u8], decoder_options: &DecoderOptions) {
+  let color_table = gif.convert_color_table(&frame.color_table, decoder_options);
+  let canvas_width = gif.lsd.width;
+  let canvas_height = gif.lsd.height;
+  let im = &frame.im;
+
+  let pixels = (im.width * im.height) as usize;
+  for (i, color_index) in frame.index_stream.iter().take(pixels).enumerate() {
+    let x = im.left + i as u32 % im.width;
+    let y = im.top + i as u32 / im.width;
+    if x >= canvas_width || y >= canvas_height || frame.is_transparent_index(*color_index) {
+      continue;
+    }
+    if let Some(color) = color_table.get(*color_index as usize) {
+      let buffer_index = (y as usize * canvas_width as usize + x as usize) * 4;
+      canvas[buffer_index] = color.red as u8;
+      canvas[buffer_index + 1] = color.green as u8;
+      canvas[buffer_index + 2] = color.blue as u8;
+      canvas[buffer_index + 3] = 255;
+    }
+  }
+
+  if decoder_options.render_plain_text.unwrap_or(false) {
+    if let Some(plain_text) = &frame.plain_text {
+      font::draw_plain_text(
+        canvas,
+        canvas_width,
+        canvas_height,
+        plain_text,
+        &color_table,
+      );
+    }
+  }
+}
+
+/// Pixels of the frame alone, the size of its image descriptor, with the transparent index at alpha `0`
+fn draw_raw(gif: &Gif, frame: &Frame, decoder_options: &DecoderOptions) -> Vec<u8> {
+  let color_table = gif.convert_color_table(&frame.color_table, decoder_options);
+  let pixels = (frame.im.width * frame.im.height) as usize;
+  let mut buffer = vec![0; pixels * 4];
+  for (i, color_index) in frame.index_stream.iter().take(pixels).enumerate() {
+    if let Some(color) = color_table.get(*color_index as usize) {
+      buffer[i * 4] = color.red as u8;
+      buffer[i * 4 + 1] = color.green as u8;
+      buffer[i * 4 + 2] = color.blue as u8;
+      buffer[i * 4 + 3] = if frame.is_transparent_index(*color_index) {
+        0
+      } else {
+        255
+      };
+    }
+  }
+  buffer
+}
+
+/// Disposal method 2, the area of the frame is cleared to DecoderOptions.background
+fn clear_rect(gif: &Gif, frame: &Frame, canvas: &mut [u8], decoder_options: &DecoderOptions) {
+  let background_color = gif.background_color(decoder_options);
+  let (left, top, width, height) = frame_rect(frame);
+  let right = left.saturating_add(width).min(gif.lsd.width);
+  let bottom = top.saturating_add(height).min(gif.lsd.height);
+  for y in top..bottom {
+    for x in left..right {
+      let buffer_index = (y as usize * gif.lsd.width as usize + x as usize) * 4;
+      canvas[buffer_index..buffer_index + 4].copy_from_slice(&background_color);
+    }
+  }
+}
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use derivative::Derivative;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::error::{Error, Result};
+
+/// How far a decode got, reported after every frame
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct Progress {
+  /// Frames parsed or composited so far
+  pub frames: u32,
+  /// Number of frames to composite, absent while parsing as it is not known yet
+  pub frame_count: Option<u32>,
+  /// Bytes of the file parsed, or of compressed image data decompressed when compositing, so far
+  pub bytes: u32,
+  /// Size of the file, or of the compressed image data of every frame when compositing
+  pub total_bytes: u32,
+}
+
+/// Flag stopping a decode with `Error::Cancelled`, clones share the flag so it can be cancelled from another thread
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+  pub fn new() -> CancellationToken {
+    CancellationToken::default()
+  }
+
+  pub fn cancel(&self) {
+    self.0.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::Relaxed)
+  }
+}
+
+/// Progress callback and cancellation of a decode, checked between frames and periodically while decompressing
+#[derive(Clone, Default, Derivative)]
+#[derivative(Debug)]
+pub struct DecodeControl {
+  #[derivative(Debug = "ignore")]
+  pub on_progress: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
+  pub cancellation_token: Option<CancellationToken>,
+}
+
+impl DecodeControl {
+  pub(crate) fn check_cancelled(&self) -> Result<()> {
+    match &self.cancellation_token {
+      Some(cancellation_token) if cancellation_token.is_cancelled() => Err(Error::Cancelled),
+      _ => Ok(()),
+    }
+  }
+
+  pub(crate) fn report(&self, progress: Progress) {
+    if let Some(on_progress) = &self.on_progress {
+      on_progress(progress);
+    }
+  }
+}
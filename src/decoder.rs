@@ -0,0 +1,824 @@
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::control::{DecodeControl, Progress};
+use crate::error::{Error, Result};
+use crate::gif::{
+  ApplicationExtension, Color, CommentExtension, Contents, Frame, Gif, GifInfo,
+  GraphicsControlExtension, ParseOptions, PlainTextExtension, UnknownExtension,
+};
+use crate::icc::IccProfile;
+use crate::lzw;
+use crate::streaming::StreamEvent;
+
+pub(crate) fn shl_or(val: u32, shift: usize, def: u32) -> u32 {
+  [val << (shift & 31), def][((shift & !31) != 0) as usize]
+}
+pub(crate) fn shr_or(val: u32, shift: usize, def: u32) -> u32 {
+  [val >> (shift & 31), def][((shift & !31) != 0) as usize]
+}
+
+pub struct Decoder {}
+
+impl Decoder {
+  pub fn decode_path<P: AsRef<Path>>(file_path: P, parse_options: &ParseOptions) -> Result<Gif> {
+    Self::decode_path_with_control(file_path, parse_options, &DecodeControl::default())
+  }
+
+  /// Same as `decode_path`, reporting progress after every frame and stopping once cancelled
+  pub fn decode_path_with_control<P: AsRef<Path>>(
+    file_path: P,
+    parse_options: &ParseOptions,
+    control: &DecodeControl,
+  ) -> Result<Gif> {
+    let contents = Self::read_path(file_path.as_ref(), parse_options)?;
+    Self::decode_contents(contents, parse_options, control)
+  }
+
+  /// Same as `decode_buffer`, but takes ownership of the contents so lazy frames keep them instead of a copy
+  pub fn decode_owned<C: AsRef<[u8]> + Send + Sync + 'static>(
+    contents: C,
+    parse_options: &ParseOptions,
+  ) -> Result<Gif> {
+    Self::decode_owned_with_control(contents, parse_options, &DecodeControl::default())
+  }
+
+  /// Same as `decode_owned`, reporting progress after every frame and stopping once cancelled
+  pub fn decode_owned_with_control<C: AsRef<[u8]> + Send + Sync + 'static>(
+    contents: C,
+    parse_options: &ParseOptions,
+    control: &DecodeControl,
+  ) -> Result<Gif> {
+    Self::decode_contents(Arc::new(contents), parse_options, control)
+  }
+
+  pub fn decode_buffer(contents: &[u8], parse_options: &ParseOptions) -> Result<Gif> {
+    Self::decode_buffer_with_control(contents, parse_options, &DecodeControl::default())
+  }
+
+  /// Same as `decode_buffer`, reporting progress after every frame and stopping once cancelled
+  pub fn decode_buffer_with_control(
+    contents: &[u8],
+    parse_options: &ParseOptions,
+    control: &DecodeControl,
+  ) -> Result<Gif> {
+    let mut gif = Self::decode_internal(contents, parse_options, control)?;
+    if parse_options.lazy.unwrap_or(false) {
+      gif.contents = Some(Arc::new(contents.to_vec()));
+    }
+    Ok(gif)
+  }
+
+  fn decode_contents(
+    contents: Contents,
+    parse_options: &ParseOptions,
+    control: &DecodeControl,
+  ) -> Result<Gif> {
+    let mut gif = Self::decode_internal((*contents).as_ref(), parse_options, control)?;
+    if parse_options.lazy.unwrap_or(false) {
+      gif.contents = Some(contents);
+    }
+    Ok(gif)
+  }
+
+  /// Walks the block structure without decompressing the image data
+  pub fn probe_path<P: AsRef<Path>>(file_path: P, parse_options: &ParseOptions) -> Result<GifInfo> {
+    let contents = Self::read_path(file_path.as_ref(), parse_options)?;
+    Self::probe_buffer((*contents).as_ref(), parse_options)
+  }
+
+  #[cfg_attr(not(feature = "mmap"), allow(unused_variables))]
+  fn read_path(file_path: &Path, parse_options: &ParseOptions) -> Result<Contents> {
+    #[cfg(feature = "mmap")]
+    if parse_options.memory_map.unwrap_or(false) {
+      if let Ok(file) = std::fs::File::open(file_path) {
+        // SAFETY: The caller opted in and guarantees the file is not modified while it is mapped
+        if let Ok(mmap) = unsafe { memmap2::Mmap::map(&file) } {
+          return Ok(Arc::new(mmap));
+        }
+      }
+    }
+    // Buffered reading, also the fallback when the file can not be mapped
+    Ok(Arc::new(std::fs::read(file_path)?))
+  }
+
+  /// Walks the block structure without decompressing the image data
+  pub fn probe_buffer(contents: &[u8], parse_options: &ParseOptions) -> Result<GifInfo> {
+    let parse_options = ParseOptions {
+      lazy: Some(true),
+      ..parse_options.clone()
+    };
+    let gif = Self::decode_internal(contents, &parse_options, &DecodeControl::default())?;
+    Ok(GifInfo::from(&gif))
+  }
+
+  fn decode_internal(
+    contents: &[u8],
+    parse_options: &ParseOptions,
+    control: &DecodeControl,
+  ) -> Result<Gif> {
+    let (mut gif, mut offset) = Self::decode_header(contents, parse_options)?;
+
+    let mut pending_gcd: Option<GraphicsControlExtension> = None;
+    loop {
+      control.check_cancelled()?;
+      match Self::decode_block(
+        &mut offset,
+        &mut gif,
+        contents,
+        &mut pending_gcd,
+        parse_options,
+        control,
+      ) {
+        Ok(Some(StreamEvent::End)) => break,
+        Ok(Some(StreamEvent::Frame(_))) => control.report(Progress {
+          frames: gif.frames.len() as u32,
+          frame_count: None,
+          bytes: offset as u32,
+          total_bytes: contents.len() as u32,
+        }),
+        Ok(_) => {}
+        Err(error) => return Err(error),
+      }
+    }
+    if !parse_options.lazy.unwrap_or(false) && parse_options.parallel.unwrap_or(false) {
+      lzw::decode_index_streams(&mut gif.frames, contents, control)?;
+    }
+    Ok(gif)
+  }
+  /// Reads the header, logical screen descriptor and global color table, returns the offset of the first block
+  pub(crate) fn decode_header(
+    contents: &[u8],
+    parse_options: &ParseOptions,
+  ) -> Result<(Gif, usize)> {
+    match contents.get(0..3) {
+      Some(b"GIF") => {}
+      Some(signature_bytes) => {
+        return Err(Error::BadSignature {
+          signature: signature_bytes.to_vec(),
+        })
+      }
+      None => return Err(Error::truncated("signature", 0, None)),
+    };
+
+    let mut gif = Gif::default();
+    match contents.get(3..6) {
+      Some(b"87a") | Some(b"89a") => {
+        gif.version = String::from_utf8_lossy(&contents[3..6]).into_owned();
+      }
+      Some(version_bytes) => {
+        gif.version = String::from_utf8_lossy(version_bytes).into_owned();
+        if parse_options.strict.unwrap_or(true) {
+          return Err(Error::UnsupportedVersion {
+            version: gif.version,
+            offset: 3,
+          });
+        }
+      }
+      None => return Err(Error::truncated("version", 3, None)),
+    }
+
+    match Self::handle_logical_screen_descriptor(&mut gif, contents) {
+      Ok(_) => {}
+      Err(error) => return Err(error),
+    }
+    if let Some(limits) = &parse_options.limits {
+      limits.check_dimensions(gif.lsd.width, gif.lsd.height, 6, None)?;
+      // Any frame is composited onto at least one canvas
+      limits.check_decoded_bytes(gif.canvas_bytes(), 6, None)?;
+    }
+
+    let mut offset: usize = 13;
+
+    // Global Color Table
+    let length: usize = (3 * 2) << gif.lsd.global_color_size;
+    let mut i: usize = offset;
+
+    if gif.lsd.global_color_flag {
+      let mut global_color_vector: Vec<Color> = Vec::new();
+
+      while i < offset + length {
+        let red = match contents.get(i) {
+          Some(red_byte) => *red_byte,
+          None => return Err(Error::truncated("red", i, None)),
+        };
+        let green = match contents.get(i + 1) {
+          Some(green_byte) => *green_byte,
+          None => return Err(Error::truncated("green", i + 1, None)),
+        };
+        let blue = match contents.get(i + 2) {
+          Some(blue_byte) => *blue_byte,
+          None => return Err(Error::truncated("blue", i + 2, None)),
+        };
+        global_color_vector.push(Color {
+          red: (red as u32),
+          green: (green as u32),
+          blue: (blue as u32),
+        });
+        i += 3;
+      }
+      Self::increment_offset(&mut offset, length);
+      gif.global_table = global_color_vector;
+    }
+    Ok((gif, offset))
+  }
+  /// Reads the block at `offset`, returns the event it produces if any
+  pub(crate) fn decode_block(
+    offset: &mut usize,
+    gif: &mut Gif,
+    contents: &[u8],
+    pending_gcd: &mut Option<GraphicsControlExtension>,
+    parse_options: &ParseOptions,
+    control: &DecodeControl,
+  ) -> Result<Option<StreamEvent>> {
+    let introducer = match contents.get(*offset) {
+      Some(introducer) => *introducer,
+      None => return Err(Error::truncated("introducer", *offset, None)),
+    };
+    Self::increment_offset(offset, 1);
+    match introducer {
+      0x2C => {
+        // Image Descriptor
+        match Self::handle_image_descriptor(
+          offset,
+          gif,
+          contents,
+          pending_gcd,
+          parse_options,
+          control,
+        ) {
+          Ok(_) => {}
+          Err(error) => return Err(error),
+        };
+        Ok(Some(StreamEvent::Frame(gif.frames.len() - 1)))
+      }
+      0x21 => {
+        let label = match contents.get(*offset) {
+          Some(label) => *label,
+          None => return Err(Error::truncated("label", *offset, None)),
+        };
+        Self::increment_offset(offset, 1);
+        match label {
+          0xF9 => {
+            match Self::handle_graphic_control_extension(offset, gif, contents, pending_gcd) {
+              Ok(_) => {}
+              Err(error) => return Err(error),
+            };
+            Ok(None)
+          }
+          0x01 => {
+            match Self::handle_plain_text_extension(
+              offset,
+              gif,
+              contents,
+              pending_gcd,
+              parse_options,
+            ) {
+              Ok(_) => {}
+              Err(error) => return Err(error),
+            };
+            Ok(Some(StreamEvent::Frame(gif.frames.len() - 1)))
+          }
+          0xFF => {
+            match Self::handle_application_extension(offset, gif, contents) {
+              Ok(_) => {}
+              Err(error) => return Err(error),
+            };
+            Ok(Some(StreamEvent::ApplicationExtension(
+              gif.application_extensions.len() - 1,
+            )))
+          }
+          0xFE => {
+            match Self::handle_comment_extension(offset, gif, contents) {
+              Ok(_) => {}
+              Err(error) => return Err(error),
+            };
+            Ok(Some(StreamEvent::Comment(gif.comments.len() - 1)))
+          }
+          _ => {
+            match Self::handle_unknown_extension(offset, gif, contents) {
+              Ok(_) => {}
+              Err(error) => return Err(error),
+            };
+            Ok(Some(StreamEvent::UnknownExtension(
+              gif.unknown_extensions.len() - 1,
+            )))
+          }
+        }
+      }
+      0x3B => {
+        // Trailer
+        if let Some(orphaned_gcd) = pending_gcd.take() {
+          gif.orphaned_gcds.push(orphaned_gcd);
+        }
+        Ok(Some(StreamEvent::End))
+      }
+      // Padding between blocks, some encoders write it and browsers skip it
+      0x00 => Ok(None),
+      _ => {
+        // Lenient mode scans forward until it finds a known introducer
+        if parse_options.strict.unwrap_or(true) {
+          return Err(Error::UnknownBlock {
+            introducer,
+            offset: *offset - 1,
+          });
+        }
+        Ok(None)
+      }
+    }
+  }
+  fn read_sub_blocks(offset: &mut usize, contents: &[u8]) -> Result<Vec<u8>> {
+    let mut data: Vec<u8> = Vec::new();
+    loop {
+      let data_sub_blocks_count = match contents.get(*offset) {
+        Some(data_sub_blocks_count_byte) => *data_sub_blocks_count_byte as usize,
+        None => return Err(Error::truncated("data_sub_blocks_count", *offset, None)),
+      };
+      Self::increment_offset(offset, 1);
+      if data_sub_blocks_count == 0 {
+        break;
+      }
+      match contents.get(*offset..*offset + data_sub_blocks_count) {
+        Some(data_sub_block) => data.extend_from_slice(data_sub_block),
+        None => return Err(Error::truncated("data_sub_block", *offset, None)),
+      };
+      Self::increment_offset(offset, data_sub_blocks_count);
+    }
+    Ok(data)
+  }
+  fn decode_text(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+      Ok(text) => text.to_string(),
+      Err(_) => data.iter().map(|byte| *byte as char).collect(),
+    }
+  }
+  fn increment_offset(offset: &mut usize, amount: usize) {
+    *offset += amount;
+  }
+  fn handle_logical_screen_descriptor(gif: &mut Gif, contents: &[u8]) -> Result<()> {
+    // Logic Screen Descriptor
+    match contents.get(6..8) {
+      Some(width_bytes) => {
+        let width = LittleEndian::read_u16(width_bytes);
+        gif.lsd.width = width as u32;
+      }
+      None => return Err(Error::truncated("width", 6, None)),
+    };
+    match contents.get(8..10) {
+      Some(height_bytes) => {
+        let height = LittleEndian::read_u16(height_bytes);
+        gif.lsd.height = height as u32;
+      }
+      None => return Err(Error::truncated("height", 8, None)),
+    };
+
+    let packed_field = match contents.get(10) {
+      Some(packed_field_bytes) => *packed_field_bytes,
+      None => return Err(Error::truncated("packed_field", 10, None)),
+    };
+
+    gif.lsd.global_color_flag = (packed_field & 0b1000_0000) != 0; // global_color_flag
+    gif.lsd.color_resolution = (packed_field & 0b0111_0000) as u32; // color_resolution
+    gif.lsd.sorted_flag = (packed_field & 0b0000_1000) != 0; // sorted_flag
+    gif.lsd.global_color_size = (packed_field & 0b0000_0111) as u32; // global_color_size
+
+    match contents.get(11) {
+      Some(background_color_index_byte) => {
+        gif.lsd.background_color_index = *background_color_index_byte as u32;
+      }
+      None => return Err(Error::truncated("background_color_index", 11, None)),
+    };
+    match contents.get(12) {
+      Some(pixel_aspect_ratio_byte) => {
+        gif.lsd.pixel_aspect_ratio = *pixel_aspect_ratio_byte as u32;
+      }
+      None => return Err(Error::truncated("pixel_aspect_ratio", 12, None)),
+    };
+    Ok(())
+  }
+  fn handle_graphic_control_extension(
+    offset: &mut usize,
+    gif: &mut Gif,
+    contents: &[u8],
+    pending_gcd: &mut Option<GraphicsControlExtension>,
+  ) -> Result<()> {
+    // Graphical Control Extension
+    let mut gcd = GraphicsControlExtension::default();
+
+    match contents.get(*offset) {
+      Some(_) => {}
+      None => {
+        return Err(Error::truncated(
+          "byte_size",
+          *offset,
+          Some(gif.frames.len()),
+        ))
+      }
+    }; // Get byte size (I dont know what this is used for)
+    Self::increment_offset(offset, 1);
+
+    let packed_field: u8 = match contents.get(*offset) {
+      Some(packed_field_bytes) => *packed_field_bytes,
+      None => {
+        return Err(Error::truncated(
+          "packed_field",
+          *offset,
+          Some(gif.frames.len()),
+        ))
+      }
+    };
+    gcd.disposal_method = shr_or((packed_field & 0b0001_1100) as u32, 2, 0);
+    gcd.user_input_flag = (packed_field & 0b0000_0010) != 0;
+    gcd.transparent_color_flag = (packed_field & 0b0000_0001) != 0;
+    Self::increment_offset(offset, 1);
+
+    match contents.get(*offset..*offset + 2) {
+      Some(delay_time_bytes) => {
+        gcd.delay_time = LittleEndian::read_u16(delay_time_bytes) as u32;
+      }
+      None => {
+        return Err(Error::truncated(
+          "delay_time",
+          *offset,
+          Some(gif.frames.len()),
+        ))
+      }
+    };
+    Self::increment_offset(offset, 2);
+
+    match contents.get(*offset) {
+      Some(transparent_color_index_bytes) => {
+        gcd.transparent_color_index = *transparent_color_index_bytes as u32;
+      }
+      None => {
+        return Err(Error::truncated(
+          "transparent_color_index",
+          *offset,
+          Some(gif.frames.len()),
+        ))
+      }
+    };
+    Self::increment_offset(offset, 1);
+
+    match contents.get(*offset) {
+      Some(_) => {}
+      None => {
+        return Err(Error::truncated(
+          "block_terminator",
+          *offset,
+          Some(gif.frames.len()),
+        ))
+      }
+    }; // Get block_terminator
+    Self::increment_offset(offset, 1);
+    // End
+
+    // The Graphic Control Extension applies to the next image or plain text
+    if let Some(orphaned_gcd) = pending_gcd.replace(gcd) {
+      gif.orphaned_gcds.push(orphaned_gcd);
+    }
+    Ok(())
+  }
+  fn handle_image_descriptor(
+    offset: &mut usize,
+    gif: &mut Gif,
+    contents: &[u8],
+    pending_gcd: &mut Option<GraphicsControlExtension>,
+    parse_options: &ParseOptions,
+    control: &DecodeControl,
+  ) -> Result<()> {
+    // Image Descriptor
+    let block_offset = *offset - 1;
+    gif.frames.push(Frame {
+      gcd: pending_gcd.take(),
+      ..Frame::default()
+    });
+    let frame_index = gif.frames.len() - 1;
+    if let Some(limits) = &parse_options.limits {
+      limits.check_frame_count(gif.frames.len(), block_offset)?;
+    }
+    let canvas_bytes = gif.canvas_bytes();
+    let parsed_frame = &mut gif.frames[frame_index];
+
+    match contents.get(*offset..*offset + 2) {
+      Some(left_bytes) => {
+        parsed_frame.im.left = LittleEndian::read_u16(left_bytes) as u32;
+      }
+      None => return Err(Error::truncated("image_left", *offset, Some(frame_index))),
+    };
+    Self::increment_offset(offset, 2);
+
+    match contents.get(*offset..*offset + 2) {
+      Some(top_bytes) => {
+        parsed_frame.im.top = LittleEndian::read_u16(top_bytes) as u32;
+      }
+      None => return Err(Error::truncated("image_top", *offset, Some(frame_index))),
+    };
+    Self::increment_offset(offset, 2);
+
+    match contents.get(*offset..*offset + 2) {
+      Some(width_bytes) => {
+        parsed_frame.im.width = LittleEndian::read_u16(width_bytes) as u32;
+      }
+      None => return Err(Error::truncated("image_width", *offset, Some(frame_index))),
+    };
+    Self::increment_offset(offset, 2);
+
+    match contents.get(*offset..*offset + 2) {
+      Some(height_bytes) => {
+        parsed_frame.im.height = LittleEndian::read_u16(height_bytes) as u32;
+      }
+      None => return Err(Error::truncated("image_height", *offset, Some(frame_index))),
+    };
+    Self::increment_offset(offset, 2);
+
+    let npix = parsed_frame.im.width as u64 * parsed_frame.im.height as u64;
+    // The index stream and the RGBA frame decode_frames composites from it
+    gif.decoded_bytes += npix + canvas_bytes;
+    if let Some(limits) = &parse_options.limits {
+      limits.check_dimensions(
+        parsed_frame.im.width,
+        parsed_frame.im.height,
+        block_offset,
+        Some(frame_index),
+      )?;
+      limits.check_decoded_bytes(gif.decoded_bytes, block_offset, Some(frame_index))?;
+    }
+
+    let packed_field = match contents.get(*offset) {
+      Some(packed_field_byte) => *packed_field_byte,
+      None => return Err(Error::truncated("packed_field", *offset, Some(frame_index))),
+    };
+    parsed_frame.im.interlace_flag = (packed_field & 0b0100_0000) != 0;
+    parsed_frame.im.sort_flag = (packed_field & 0b0010_0000) != 0;
+    // let _ = (packed_field & 0b0001_1000) as u8; // Future use
+    Self::increment_offset(offset, 1);
+    // End
+
+    // Local Color Table (Check local color table flag)
+    if (packed_field & 0b1000_0000) != 0 {
+      let length: usize = (3 * 2) << (packed_field & 0b0000_0111) as u32;
+      let mut i: usize = *offset;
+      let mut local_color_vector: Vec<Color> = Vec::new();
+
+      while i < *offset + length {
+        let red = match contents.get(i) {
+          Some(red_byte) => *red_byte,
+          None => return Err(Error::truncated("red", i, Some(frame_index))),
+        };
+        let green = match contents.get(i + 1) {
+          Some(green_byte) => *green_byte,
+          None => return Err(Error::truncated("green", i + 1, Some(frame_index))),
+        };
+        let blue = match contents.get(i + 2) {
+          Some(blue_byte) => *blue_byte,
+          None => return Err(Error::truncated("blue", i + 2, Some(frame_index))),
+        };
+        local_color_vector.push(Color {
+          red: (red as u32),
+          green: (green as u32),
+          blue: (blue as u32),
+        });
+        i += 3;
+      }
+      Self::increment_offset(offset, length);
+      parsed_frame.color_table = local_color_vector;
+    } else {
+      parsed_frame.color_table = gif.global_table.to_vec();
+    }
+    // Initialize GIF data stream decoder.
+    let lzw_minimum_code_size = match contents.get(*offset) {
+      Some(lzw_minimum_code_size_byte) => *lzw_minimum_code_size_byte,
+      None => {
+        return Err(Error::truncated(
+          "lzw_minimum_code_size",
+          *offset,
+          Some(frame_index),
+        ))
+      }
+    };
+    if lzw_minimum_code_size > lzw::MAX_LZW_MINIMUM_CODE_SIZE {
+      return Err(Error::InvalidLzwMinimumCodeSize {
+        code_size: lzw_minimum_code_size,
+        offset: *offset,
+        frame_index,
+      });
+    }
+    Self::increment_offset(offset, 1);
+
+    // Record where the data sub-blocks are, up to the block terminator
+    let mut data_ranges: Vec<Range<usize>> = Vec::new();
+    loop {
+      let data_sub_blocks_count = match contents.get(*offset) {
+        Some(data_sub_blocks_count_byte) => *data_sub_blocks_count_byte as usize,
+        None => {
+          return Err(Error::truncated(
+            "data_sub_blocks_count",
+            *offset,
+            Some(frame_index),
+          ))
+        }
+      };
+      Self::increment_offset(offset, 1);
+      if data_sub_blocks_count == 0 {
+        break;
+      }
+      if contents.len() < *offset + data_sub_blocks_count {
+        return Err(Error::truncated("block", *offset, Some(frame_index)));
+      }
+      data_ranges.push(*offset..*offset + data_sub_blocks_count);
+      Self::increment_offset(offset, data_sub_blocks_count);
+    }
+    if let Some(limits) = &parse_options.limits {
+      let compressed_bytes = data_ranges.iter().map(|range| range.len()).sum();
+      limits.check_compression_ratio(npix, compressed_bytes, block_offset, frame_index)?;
+    }
+    parsed_frame.lzw_minimum_code_size = lzw_minimum_code_size;
+    parsed_frame.data_ranges = data_ranges;
+
+    // Lazily parsed frames are decompressed when they are decoded, parallel ones once all are parsed
+    if !parse_options.lazy.unwrap_or(false) && !parse_options.parallel.unwrap_or(false) {
+      parsed_frame.index_stream =
+        lzw::decode_index_stream(parsed_frame, contents, frame_index, control)?;
+    }
+    Ok(())
+  }
+  fn handle_plain_text_extension(
+    offset: &mut usize,
+    gif: &mut Gif,
+    contents: &[u8],
+    pending_gcd: &mut Option<GraphicsControlExtension>,
+    parse_options: &ParseOptions,
+  ) -> Result<()> {
+    // Plain Text Extension (Optional)
+    gif.decoded_bytes += gif.canvas_bytes();
+    if let Some(limits) = &parse_options.limits {
+      limits.check_frame_count(gif.frames.len() + 1, *offset - 2)?;
+      limits.check_decoded_bytes(gif.decoded_bytes, *offset - 2, Some(gif.frames.len()))?;
+    }
+
+    let block_size = match contents.get(*offset) {
+      Some(block_size_byte) => *block_size_byte as usize,
+      None => {
+        return Err(Error::truncated(
+          "block_size",
+          *offset,
+          Some(gif.frames.len()),
+        ))
+      }
+    };
+    Self::increment_offset(offset, 1);
+
+    let mut plain_text = PlainTextExtension::default();
+    match contents.get(*offset..*offset + 12) {
+      Some(plain_text_bytes) if block_size >= 12 => {
+        plain_text.text_grid_left = LittleEndian::read_u16(&plain_text_bytes[0..2]) as u32;
+        plain_text.text_grid_top = LittleEndian::read_u16(&plain_text_bytes[2..4]) as u32;
+        plain_text.text_grid_width = LittleEndian::read_u16(&plain_text_bytes[4..6]) as u32;
+        plain_text.text_grid_height = LittleEndian::read_u16(&plain_text_bytes[6..8]) as u32;
+        plain_text.character_cell_width = plain_text_bytes[8] as u32;
+        plain_text.character_cell_height = plain_text_bytes[9] as u32;
+        plain_text.text_foreground_color_index = plain_text_bytes[10] as u32;
+        plain_text.text_background_color_index = plain_text_bytes[11] as u32;
+      }
+      _ => {
+        return Err(Error::truncated(
+          "text_grid",
+          *offset,
+          Some(gif.frames.len()),
+        ))
+      }
+    };
+    Self::increment_offset(offset, block_size);
+
+    let data = Self::read_sub_blocks(offset, contents)?;
+    plain_text.text = Self::decode_text(&data);
+
+    gif.frames.push(Frame {
+      gcd: pending_gcd.take(),
+      color_table: gif.global_table.to_vec(),
+      plain_text: Some(plain_text),
+      ..Frame::default()
+    });
+    Ok(())
+  }
+  fn handle_application_extension(
+    offset: &mut usize,
+    gif: &mut Gif,
+    contents: &[u8],
+  ) -> Result<()> {
+    // Application Extension (Optional)
+
+    // Offset of the extension introducer, before the label
+    let extension_offset = *offset - 2;
+
+    let block_size = match contents.get(*offset) {
+      Some(block_size_byte) => *block_size_byte as usize,
+      None => return Err(Error::truncated("block_size", *offset, None)),
+    };
+    Self::increment_offset(offset, 1);
+
+    let length = *offset + block_size;
+    let application = match contents.get(*offset..length) {
+      Some(application_bytes) => application_bytes,
+      None => return Err(Error::truncated("application", *offset, None)),
+    };
+    Self::increment_offset(offset, block_size);
+
+    let data_offset = *offset;
+    let data = Self::read_sub_blocks(offset, contents)?;
+
+    if application == b"NETSCAPE2.0" || application == b"ANIMEXTS1.0" {
+      let mut looping_offset = data_offset;
+      Self::handle_looping_sub_blocks(&mut looping_offset, gif, contents)?;
+    }
+
+    if application == b"ICCRGBG1012" {
+      gif.icc = IccProfile::parse(&data);
+      gif.icc_profile = Some(data.clone());
+    }
+
+    let xmp = if application == b"XMP DataXMP" {
+      Some(Self::decode_xmp_packet(&contents[data_offset..*offset]))
+    } else {
+      None
+    };
+
+    // Split before decoding, a replaced invalid byte takes more than one byte once decoded
+    let (identifier, authentication_code) = application.split_at(application.len().min(8));
+    gif.application_extensions.push(ApplicationExtension {
+      offset: extension_offset as u32,
+      identifier: String::from_utf8_lossy(identifier).into_owned(),
+      authentication_code: String::from_utf8_lossy(authentication_code).into_owned(),
+      data,
+      xmp,
+    });
+    Ok(())
+  }
+  fn decode_xmp_packet(raw_bytes: &[u8]) -> String {
+    // The XMP packet is stored as raw bytes rather than data sub-blocks, followed by a
+    // 258 byte "magic trailer" (0x01, 0xFF, 0xFE, ..., 0x00, 0x00) which lets sub-block
+    // readers skip over it. 0xFF and 0xFE never occur in UTF-8, so the trailer starts at
+    // the first 0x01 0xFF 0xFE sequence.
+    let packet_length = raw_bytes
+      .windows(3)
+      .position(|window| window == [0x01, 0xFF, 0xFE])
+      .unwrap_or(raw_bytes.len());
+    String::from_utf8_lossy(&raw_bytes[..packet_length]).into_owned()
+  }
+  fn handle_looping_sub_blocks(offset: &mut usize, gif: &mut Gif, contents: &[u8]) -> Result<()> {
+    // NETSCAPE2.0 / ANIMEXTS1.0 Sub-blocks
+    loop {
+      let sub_block_size = match contents.get(*offset) {
+        Some(sub_block_size_byte) => *sub_block_size_byte as usize,
+        None => return Err(Error::truncated("sub_block_size", *offset, None)),
+      };
+      Self::increment_offset(offset, 1);
+      if sub_block_size == 0 {
+        break;
+      }
+
+      let sub_block = match contents.get(*offset..*offset + sub_block_size) {
+        Some(sub_block_bytes) => sub_block_bytes,
+        None => return Err(Error::truncated("sub_block", *offset, None)),
+      };
+      match sub_block[0] {
+        // Loop Count Sub-block
+        1 if sub_block_size >= 3 => {
+          gif.loop_count = Some(LittleEndian::read_u16(&sub_block[1..3]) as u32);
+        }
+        // Buffering Sub-block
+        2 if sub_block_size >= 5 => {
+          gif.buffer_size = Some(LittleEndian::read_u32(&sub_block[1..5]));
+        }
+        _ => {}
+      }
+      Self::increment_offset(offset, sub_block_size);
+    }
+    Ok(())
+  }
+  fn handle_unknown_extension(offset: &mut usize, gif: &mut Gif, contents: &[u8]) -> Result<()> {
+    // Unknown Extension, only its data sub-blocks can be read
+    let extension_offset = *offset - 2;
+    let data = Self::read_sub_blocks(offset, contents)?;
+    gif.unknown_extensions.push(UnknownExtension {
+      offset: extension_offset as u32,
+      label: contents[extension_offset + 1] as u32,
+      data,
+    });
+    Ok(())
+  }
+  fn handle_comment_extension(offset: &mut usize, gif: &mut Gif, contents: &[u8]) -> Result<()> {
+    // Comment Extension (Optional)
+    let data = Self::read_sub_blocks(offset, contents)?;
+    gif.comments.push(CommentExtension {
+      frame_index: gif.frames.len() as u32,
+      text: Self::decode_text(&data),
+      data,
+    });
+    Ok(())
+  }
+}
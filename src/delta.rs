@@ -0,0 +1,84 @@
+use crate::compositor::{self, DISPOSAL_BACKGROUND, DISPOSAL_PREVIOUS};
+use crate::font;
+use crate::gif::{DecoderOptions, Gif, PlainTextExtension};
+
+/// What to do with the area of a frame once it was displayed, before drawing the next frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposal {
+  /// Leave the frame on the canvas, disposal methods 0 | 1 and the undefined ones
+  Keep,
+  /// Clear the area to DecoderOptions.background, disposal method 2
+  Background,
+  /// Restore the area to what it was before the frame was drawn, disposal method 3 unless DecoderOptions.implementDisposalPrevious is disabled
+  Previous,
+}
+
+/// A frame as the area it draws onto the canvas, returned by `Gif::decode_deltas`
+#[derive(Debug, Clone)]
+pub struct FrameDelta {
+  /// Position of the area on the canvas, clipped to the logical screen
+  pub left: u32,
+  pub top: u32,
+  pub width: u32,
+  pub height: u32,
+  /// RGBA of the area, transparent black where the frame leaves the canvas as it is
+  pub pixels: Vec<u8>,
+  pub disposal: Disposal,
+}
+
+impl FrameDelta {
+  /// The frame must already have its index stream decompressed
+  pub(crate) fn new(gif: &Gif, frame_index: usize, decoder_options: &DecoderOptions) -> FrameDelta {
+    let frame = &gif.frames[frame_index];
+    let (left, top, width, height) = compositor::frame_rect(frame);
+    let left = left.min(gif.lsd.width);
+    let top = top.min(gif.lsd.height);
+    let width = width.min(gif.lsd.width - left);
+    let height = height.min(gif.lsd.height - top);
+    let mut pixels = vec![0; (width * height) as usize * 4];
+
+    let color_table = gif.convert_color_table(&frame.color_table, decoder_options);
+    let im = &frame.im;
+    let frame_pixels = (im.width * im.height) as usize;
+    for (i, color_index) in frame.index_stream.iter().take(frame_pixels).enumerate() {
+      let x = im.left + i as u32 % im.width;
+      let y = im.top + i as u32 / im.width;
+      if x >= left + width || y >= top + height || frame.is_transparent_index(*color_index) {
+        continue;
+      }
+      if let Some(color) = color_table.get(*color_index as usize) {
+        let buffer_index = ((y - top) as usize * width as usize + (x - left) as usize) * 4;
+        pixels[buffer_index] = color.red as u8;
+        pixels[buffer_index + 1] = color.green as u8;
+        pixels[buffer_index + 2] = color.blue as u8;
+        pixels[buffer_index + 3] = 255;
+      }
+    }
+
+    if decoder_options.render_plain_text.unwrap_or(false) {
+      if let Some(plain_text) = &frame.plain_text {
+        // The area is the text grid, so the text is drawn from its top left corner
+        let plain_text = PlainTextExtension {
+          text_grid_left: 0,
+          text_grid_top: 0,
+          ..plain_text.clone()
+        };
+        font::draw_plain_text(&mut pixels, width, height, &plain_text, &color_table);
+      }
+    }
+
+    let disposal = match compositor::disposal_method(frame, decoder_options) {
+      DISPOSAL_BACKGROUND => Disposal::Background,
+      DISPOSAL_PREVIOUS => Disposal::Previous,
+      _ => Disposal::Keep,
+    };
+    FrameDelta {
+      left,
+      top,
+      width,
+      height,
+      pixels,
+      disposal,
+    }
+  }
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+
+/// Error returned when a Gif can not be read or decoded
+#[derive(Debug)]
+pub enum Error {
+  /// The file ended before a field of a block could be read
+  TruncatedBlock {
+    field: &'static str,
+    offset: usize,
+    frame_index: Option<usize>,
+  },
+  /// The file does not start with `GIF`
+  BadSignature { signature: Vec<u8> },
+  /// The version following the signature is not supported
+  UnsupportedVersion { version: String, offset: usize },
+  /// A block starts with an introducer that is not an extension, image or trailer
+  UnknownBlock { introducer: u8, offset: usize },
+  /// The LZW minimum code size of a frame is larger than the code table allows
+  InvalidLzwMinimumCodeSize {
+    code_size: u8,
+    offset: usize,
+    frame_index: usize,
+  },
+  /// The LZW data of a frame contains a code that is not in the code table
+  InvalidLzwCode {
+    code: u32,
+    offset: usize,
+    frame_index: usize,
+  },
+  /// The requested frame does not exist
+  FrameOutOfBounds {
+    frame_index: usize,
+    frame_count: usize,
+  },
+  /// One of the decoder limits was exceeded
+  LimitsExceeded {
+    limit: &'static str,
+    offset: usize,
+    frame_index: Option<usize>,
+  },
+  /// The decode was stopped with its cancellation token
+  Cancelled,
+  /// The file could not be read
+  Io(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+  pub(crate) fn truncated(field: &'static str, offset: usize, frame_index: Option<usize>) -> Error {
+    Error::TruncatedBlock {
+      field,
+      offset,
+      frame_index,
+    }
+  }
+
+  /// Stable identifier of the error kind, exposed as `code` on JS errors
+  pub fn code(&self) -> &'static str {
+    match self {
+      Error::TruncatedBlock { .. } => "ERR_GIF_TRUNCATED_BLOCK",
+      Error::BadSignature { .. } => "ERR_GIF_BAD_SIGNATURE",
+      Error::UnsupportedVersion { .. } => "ERR_GIF_UNSUPPORTED_VERSION",
+      Error::UnknownBlock { .. } => "ERR_GIF_UNKNOWN_BLOCK",
+      Error::InvalidLzwMinimumCodeSize { .. } => "ERR_GIF_INVALID_LZW_MINIMUM_CODE_SIZE",
+      Error::InvalidLzwCode { .. } => "ERR_GIF_INVALID_LZW_CODE",
+      Error::FrameOutOfBounds { .. } => "ERR_GIF_FRAME_OUT_OF_BOUNDS",
+      Error::LimitsExceeded { .. } => "ERR_GIF_LIMITS_EXCEEDED",
+      Error::Cancelled => "ERR_GIF_CANCELLED",
+      Error::Io(_) => "ERR_GIF_IO",
+    }
+  }
+
+  /// Byte offset in the file where the error happened, if any
+  pub fn offset(&self) -> Option<usize> {
+    match self {
+      Error::TruncatedBlock { offset, .. }
+      | Error::UnsupportedVersion { offset, .. }
+      | Error::UnknownBlock { offset, .. }
+      | Error::InvalidLzwMinimumCodeSize { offset, .. }
+      | Error::InvalidLzwCode { offset, .. }
+      | Error::LimitsExceeded { offset, .. } => Some(*offset),
+      Error::BadSignature { .. } => Some(0),
+      Error::FrameOutOfBounds { .. } | Error::Cancelled | Error::Io(_) => None,
+    }
+  }
+
+  /// Index of the frame the error happened in, if any
+  pub fn frame_index(&self) -> Option<usize> {
+    match self {
+      Error::TruncatedBlock { frame_index, .. } | Error::LimitsExceeded { frame_index, .. } => {
+        *frame_index
+      }
+      Error::InvalidLzwMinimumCodeSize { frame_index, .. }
+      | Error::InvalidLzwCode { frame_index, .. }
+      | Error::FrameOutOfBounds { frame_index, .. } => Some(*frame_index),
+      Error::BadSignature { .. }
+      | Error::UnsupportedVersion { .. }
+      | Error::UnknownBlock { .. }
+      | Error::Cancelled
+      | Error::Io(_) => None,
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::TruncatedBlock {
+        field,
+        offset,
+        frame_index,
+      } => {
+        write!(f, "Unable to get {} at offset {}", field, offset)?;
+        if let Some(frame_index) = frame_index {
+          write!(f, " in frame {}", frame_index)?;
+        }
+        f.write_str(", the file is corrupted")
+      }
+      Error::BadSignature { signature } => write!(
+        f,
+        "Invalid file signature, got {}",
+        String::from_utf8_lossy(signature)
+      ),
+      Error::UnsupportedVersion { version, offset } => {
+        write!(f, "Unsupported version {} at offset {}", version, offset)
+      }
+      Error::UnknownBlock { introducer, offset } => write!(
+        f,
+        "Unknown block introducer 0x{:02X} at offset {}",
+        introducer, offset
+      ),
+      Error::InvalidLzwMinimumCodeSize {
+        code_size,
+        offset,
+        frame_index,
+      } => write!(
+        f,
+        "Invalid LZW minimum code size {} at offset {} in frame {}",
+        code_size, offset, frame_index
+      ),
+      Error::InvalidLzwCode {
+        code,
+        offset,
+        frame_index,
+      } => write!(
+        f,
+        "Invalid LZW code {} at offset {} in frame {}",
+        code, offset, frame_index
+      ),
+      Error::FrameOutOfBounds {
+        frame_index,
+        frame_count,
+      } => write!(
+        f,
+        "Frame index {} out of bounds, the gif has {} frames",
+        frame_index, frame_count
+      ),
+      Error::LimitsExceeded {
+        limit,
+        offset,
+        frame_index,
+      } => {
+        write!(f, "Decoder limit {} exceeded at offset {}", limit, offset)?;
+        if let Some(frame_index) = frame_index {
+          write!(f, " in frame {}", frame_index)?;
+        }
+        Ok(())
+      }
+      Error::Cancelled => write!(f, "Decoding was cancelled"),
+      Error::Io(error) => write!(f, "{}", error),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Error::Io(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+impl From<std::io::Error> for Error {
+  fn from(error: std::io::Error) -> Error {
+    Error::Io(error)
+  }
+}
@@ -0,0 +1,30 @@
+use crate::compositor::Compositor;
+use crate::control::DecodeControl;
+use crate::error::Result;
+use crate::gif::{DecoderOptions, Gif};
+
+/// Iterator over the composited frames of a Gif, created by `Gif::iter_frames`
+pub struct FrameIter<'a> {
+  gif: &'a mut Gif,
+  compositor: Compositor,
+}
+
+impl<'a> FrameIter<'a> {
+  pub(crate) fn new(gif: &'a mut Gif, decoder_options: &DecoderOptions) -> FrameIter<'a> {
+    let compositor = Compositor::without_cache(decoder_options);
+    FrameIter { gif, compositor }
+  }
+}
+
+impl Iterator for FrameIter<'_> {
+  type Item = Result<Vec<u8>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.compositor.next(self.gif, &DecodeControl::default())
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let remaining = self.compositor.remaining(self.gif);
+    (remaining, Some(remaining))
+  }
+}
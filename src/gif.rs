@@ -0,0 +1,527 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+
+use derivative::Derivative;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::cache::{CacheStats, FrameCache};
+use crate::compositor::{Compositor, DISPOSAL_UNSPECIFIED};
+use crate::control::{DecodeControl, Progress};
+use crate::delta::FrameDelta;
+use crate::error::{Error, Result};
+use crate::frame_iter::FrameIter;
+use crate::icc::IccProfile;
+use crate::limits::DecoderLimits;
+use crate::lzw;
+use crate::seek::SeekIndex;
+
+/// Bytes of the file, kept alive while the frames of a lazily parsed Gif are decoded
+pub(crate) type Contents = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+#[derive(Default, Derivative, Clone)]
+#[derivative(Debug)]
+pub struct Gif {
+  pub version: String,
+  pub lsd: LogicalScreenDescriptor,
+  pub global_table: Vec<Color>,
+  pub frames: Vec<Frame>,
+  /// Number of times the animation repeats, taken from the NETSCAPE2.0 / ANIMEXTS1.0 extension, `0` means loop forever
+  pub loop_count: Option<u32>,
+  /// Suggested buffer size in bytes, taken from the NETSCAPE2.0 / ANIMEXTS1.0 extension
+  pub buffer_size: Option<u32>,
+  pub comments: Vec<CommentExtension>,
+  pub application_extensions: Vec<ApplicationExtension>,
+  /// Extensions with a label the decoder does not know, their data sub-blocks are skipped
+  pub unknown_extensions: Vec<UnknownExtension>,
+  /// Graphic Control Extensions that were not followed by an image or plain text
+  pub orphaned_gcds: Vec<GraphicsControlExtension>,
+  /// ICC colour profile, taken from the ICCRGBG1 extension
+  pub icc_profile: Option<Vec<u8>>,
+  pub(crate) icc: Option<IccProfile>,
+  /// Contents of the file, kept when parsed with ParseOptions.lazy to decode the index streams on demand
+  #[derivative(Debug = "ignore")]
+  pub(crate) contents: Option<Contents>,
+  /// Bytes of the index streams and of a composited frame per frame, checked against DecoderLimits.maxDecodedBytes
+  pub(crate) decoded_bytes: u64,
+  /// Keyframes and checkpoints `decode_frame` seeks from, built on the first call
+  #[derivative(Debug = "ignore")]
+  pub(crate) seek_index: Option<SeekIndex>,
+  /// Order the frames are evicted from the cache in
+  #[derivative(Debug = "ignore")]
+  pub(crate) cache: FrameCache,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct DecoderOptions {
+  /// Whether to implement the disposal method of the previous frame, default is `true`
+  pub implement_disposal_previous: bool,
+  /// Whether to store the cache of the frame, should be `false` when enabling disableDisposalMethods | rawDecode, default is `true`
+  pub store_cache: bool,
+  /// Whether to disable the use of any disposal methods, default is `false`
+  pub disable_disposal_methods: bool,
+  /// Whether to return the raw decoded frame, also disables the use of any disposal methods, default is `false`
+  pub raw_decode: bool,
+  /// Whether to draw the text of Plain Text Extensions onto the frames using the built-in 8x8 font, default is `false`
+  pub render_plain_text: Option<bool>,
+  /// Whether to convert the colour tables into sRGB using the embedded ICC profile before compositing, default is `false`
+  pub convert_to_srgb: Option<bool>,
+  /// Whether to free the index streams once the frames are decoded, only applies to Gifs parsed with ParseOptions.lazy as they can be decompressed again, default is `false`
+  pub free_index_streams: Option<bool>,
+  /// Colour of the canvas before the first frame and of the areas disposal method 2 clears, default is transparent
+  pub background: Option<Background>,
+  /// Bytes of composited canvases decodeFrame keeps to seek from instead of compositing every previous frame, default is `16777216`
+  pub checkpoint_budget: Option<u32>,
+  /// Bytes of cachedFrame and previousPixels kept with storeCache, the least recently used frames are evicted first, default is no limit
+  pub cache_budget: Option<u32>,
+}
+
+impl Default for DecoderOptions {
+  fn default() -> DecoderOptions {
+    DecoderOptions {
+      implement_disposal_previous: true,
+      store_cache: true,
+      disable_disposal_methods: false,
+      raw_decode: false,
+      render_plain_text: None,
+      convert_to_srgb: None,
+      free_index_streams: None,
+      background: None,
+      checkpoint_budget: None,
+      cache_budget: None,
+    }
+  }
+}
+
+/// Colour of the canvas before the first frame and of the areas disposal method 2 clears
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Background {
+  /// Transparent black, like browsers display it
+  #[default]
+  Transparent,
+  /// Colour at LogicalScreenDescriptor.backgroundColorIndex in the global colour table, opaque, transparent without a global colour table
+  LogicalScreen,
+  /// RGBA colour
+  Custom([u8; 4]),
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ParseOptions {
+  /// Whether to reject files with a version other than `87a` | `89a` or with unknown block introducers, browsers still display them so disable this to read them anyway, default is `true`
+  pub strict: Option<bool>,
+  /// Whether to only record where the compressed image data is and decompress the index streams when the frames are decoded, default is `false`
+  pub lazy: Option<bool>,
+  /// Whether to decompress the index streams of the frames in parallel on a thread pool once the file is parsed, ignored with lazy, default is `false`
+  pub parallel: Option<bool>,
+  /// Whether to memory map the file in decodePath | probePath instead of reading it into memory, falls back to reading when the file can not be mapped, the file must not be modified while it is mapped, default is `false`
+  pub memory_map: Option<bool>,
+  /// Limits on the dimensions, frame count and decompressed size, failing with ERR_GIF_LIMITS_EXCEEDED, default is no limits
+  pub limits: Option<DecoderLimits>,
+}
+
+impl Gif {
+  pub fn decode_frames(&mut self, decoder_options: &DecoderOptions) -> Result<Vec<Vec<u8>>> {
+    self.decode_frames_with_control(decoder_options, &DecodeControl::default())
+  }
+
+  /// Same as `decode_frames`, reporting progress after every frame and stopping once cancelled
+  pub fn decode_frames_with_control(
+    &mut self,
+    decoder_options: &DecoderOptions,
+    control: &DecodeControl,
+  ) -> Result<Vec<Vec<u8>>> {
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+
+    let total_bytes: usize = self.frames.iter().map(Frame::compressed_size).sum();
+    let mut bytes = 0;
+
+    let mut compositor = Compositor::new(decoder_options);
+    for i in 0..self.frames.len() {
+      control.check_cancelled()?;
+      match compositor.next(self, control) {
+        Some(Ok(buffer)) => buffers.push(buffer),
+        Some(Err(error)) => return Err(error),
+        None => break,
+      }
+
+      bytes += self.frames[i].compressed_size();
+      control.report(Progress {
+        frames: (i + 1) as u32,
+        frame_count: Some(self.frames.len() as u32),
+        bytes: bytes as u32,
+        total_bytes: total_bytes as u32,
+      });
+    }
+    Ok(buffers)
+  }
+
+  /// Composites the frames one at a time, only the canvas the next frame is drawn onto is kept,
+  /// frames are never cached regardless of DecoderOptions.storeCache
+  pub fn iter_frames(&mut self, decoder_options: &DecoderOptions) -> FrameIter<'_> {
+    FrameIter::new(self, decoder_options)
+  }
+
+  /// Composites the frames from the closest keyframe, checkpoint or cached frame before `frame_index`
+  pub fn decode_frame(
+    &mut self,
+    frame_index: usize,
+    decoder_options: &DecoderOptions,
+  ) -> Result<Vec<u8>> {
+    if frame_index >= self.frames.len() {
+      return Err(Error::FrameOutOfBounds {
+        frame_index,
+        frame_count: self.frames.len(),
+      });
+    }
+
+    self.cache.validate(&mut self.frames, decoder_options);
+    if let Some(cached_frame) = &self.frames[frame_index].cached_frame {
+      let buffer = cached_frame.to_owned();
+      self.cache.touch(frame_index);
+      self.cache.hits += 1;
+      return Ok(buffer);
+    }
+    self.cache.misses += 1;
+
+    let mut compositor = Compositor::seek(self, decoder_options, frame_index);
+    let control = DecodeControl::default();
+    loop {
+      let buffer = match compositor.next(self, &control) {
+        Some(result) => result?,
+        None => unreachable!("the compositor stops at the last frame"),
+      };
+      if compositor.frame_index() > frame_index {
+        return Ok(buffer);
+      }
+    }
+  }
+
+  /// Every frame as the area it draws, where to draw it and how to dispose of it, for compositing the frames elsewhere,
+  /// drawing `pixels` where they are opaque onto the canvas then applying `disposal` composites the same frames as `decode_frames`
+  pub fn decode_deltas(&mut self, decoder_options: &DecoderOptions) -> Result<Vec<FrameDelta>> {
+    let mut deltas = Vec::new();
+    for frame_index in 0..self.frames.len() {
+      deltas.push(self.decode_delta(frame_index, decoder_options)?);
+    }
+    Ok(deltas)
+  }
+
+  pub fn decode_delta(
+    &mut self,
+    frame_index: usize,
+    decoder_options: &DecoderOptions,
+  ) -> Result<FrameDelta> {
+    if frame_index >= self.frames.len() {
+      return Err(Error::FrameOutOfBounds {
+        frame_index,
+        frame_count: self.frames.len(),
+      });
+    }
+    self.decompress_index_stream(frame_index, &DecodeControl::default())?;
+    let delta = FrameDelta::new(self, frame_index, decoder_options);
+    self.free_index_stream(frame_index, decoder_options);
+    Ok(delta)
+  }
+
+  /// Drops every cached frame and checkpoint, the next decodes composite the frames again
+  pub fn clear_cache(&mut self) {
+    self.cache.clear(&mut self.frames);
+    if let Some(seek_index) = &mut self.seek_index {
+      seek_index.clear_checkpoints();
+    }
+  }
+
+  pub fn cache_stats(&self) -> CacheStats {
+    let cached_frames = self
+      .frames
+      .iter()
+      .filter(|frame| frame.cached_frame.is_some() || frame.previous_pixels.is_some());
+    let bytes: usize = cached_frames
+      .clone()
+      .map(|frame| {
+        frame.cached_frame.as_ref().map_or(0, Vec::len)
+          + frame.previous_pixels.as_ref().map_or(0, Vec::len)
+      })
+      .sum();
+    let (checkpoints, checkpoint_bytes) = match &self.seek_index {
+      Some(seek_index) => seek_index.checkpoint_stats(),
+      None => (0, 0),
+    };
+    CacheStats {
+      frames: cached_frames.count() as u32,
+      bytes: u32::try_from(bytes).unwrap_or(u32::MAX),
+      checkpoints: checkpoints as u32,
+      checkpoint_bytes: u32::try_from(checkpoint_bytes).unwrap_or(u32::MAX),
+      hits: self.cache.hits,
+      misses: self.cache.misses,
+      evictions: self.cache.evictions,
+    }
+  }
+
+  /// Frames composited the same onto an empty canvas as onto the previous frames, decodeFrame never composites frames before the closest one
+  pub fn keyframes(&mut self, decoder_options: &DecoderOptions) -> &[usize] {
+    self.seek_index(decoder_options).keyframes()
+  }
+
+  pub(crate) fn seek_index(&mut self, decoder_options: &DecoderOptions) -> &mut SeekIndex {
+    match self.seek_index.take() {
+      Some(seek_index) if seek_index.matches(decoder_options) => self.seek_index.insert(seek_index),
+      _ => {
+        let seek_index = SeekIndex::new(self, decoder_options);
+        self.seek_index.insert(seek_index)
+      }
+    }
+  }
+
+  /// Returns the index stream of the frame, decompressing it first when parsed with ParseOptions.lazy
+  pub fn index_stream(&mut self, frame_index: usize) -> Result<&[u8]> {
+    if frame_index >= self.frames.len() {
+      return Err(Error::FrameOutOfBounds {
+        frame_index,
+        frame_count: self.frames.len(),
+      });
+    }
+    self.decompress_index_stream(frame_index, &DecodeControl::default())?;
+    Ok(&self.frames[frame_index].index_stream)
+  }
+
+  pub(crate) fn decompress_index_stream(
+    &mut self,
+    frame_index: usize,
+    control: &DecodeControl,
+  ) -> Result<()> {
+    let frame = &self.frames[frame_index];
+    if !frame.index_stream.is_empty() || frame.data_ranges.is_empty() {
+      return Ok(());
+    }
+    if let Some(contents) = &self.contents {
+      let index_stream =
+        lzw::decode_index_stream(frame, (**contents).as_ref(), frame_index, control)?;
+      self.frames[frame_index].index_stream = index_stream;
+    }
+    Ok(())
+  }
+
+  pub(crate) fn free_index_stream(&mut self, frame_index: usize, decoder_options: &DecoderOptions) {
+    if self.contents.is_none() || !decoder_options.free_index_streams.unwrap_or(false) {
+      return;
+    }
+    let frame = &mut self.frames[frame_index];
+    if !frame.data_ranges.is_empty() {
+      frame.index_stream = Vec::new();
+    }
+  }
+
+  pub(crate) fn convert_color_table<'a>(
+    &self,
+    color_table: &'a [Color],
+    decoder_options: &DecoderOptions,
+  ) -> Cow<'a, [Color]> {
+    match &self.icc {
+      Some(icc) if decoder_options.convert_to_srgb.unwrap_or(false) => {
+        Cow::Owned(color_table.iter().map(|color| icc.to_srgb(color)).collect())
+      }
+      _ => Cow::Borrowed(color_table),
+    }
+  }
+
+  /// Bytes of an RGBA canvas the size of the logical screen
+  pub(crate) fn canvas_bytes(&self) -> u64 {
+    self.lsd.width as u64 * self.lsd.height as u64 * 4
+  }
+
+  pub(crate) fn fill_with_empty_color(
+    &self,
+    buffer: &mut Vec<u8>,
+    decoder_options: &DecoderOptions,
+  ) {
+    let background_color = self.background_color(decoder_options);
+    for _ in 0..(self.lsd.width * self.lsd.height) {
+      buffer.extend_from_slice(&background_color);
+    }
+  }
+
+  /// RGBA of DecoderOptions.background
+  pub(crate) fn background_color(&self, decoder_options: &DecoderOptions) -> [u8; 4] {
+    match decoder_options.background.unwrap_or_default() {
+      Background::Transparent => [0, 0, 0, 0],
+      Background::LogicalScreen => {
+        let index = self.lsd.background_color_index as usize;
+        match self.global_table.get(index..=index) {
+          Some(color) => {
+            let color = &self.convert_color_table(color, decoder_options)[0];
+            [color.red as u8, color.green as u8, color.blue as u8, 255]
+          }
+          None => [0, 0, 0, 0],
+        }
+      }
+      Background::Custom(color) => color,
+    }
+  }
+}
+
+/// Summary of a Gif, returned by Decoder.probePath | Decoder.probeBuffer
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct GifInfo {
+  pub width: u32,
+  pub height: u32,
+  pub frame_count: u32,
+  /// Sum of the frame delays in milliseconds, saturating at 4294967295
+  pub total_duration: u32,
+  /// Number of times the animation repeats, `0` means loop forever
+  pub loop_count: Option<u32>,
+}
+
+impl From<&Gif> for GifInfo {
+  fn from(gif: &Gif) -> GifInfo {
+    let total_duration: u64 = gif
+      .frames
+      .iter()
+      .filter_map(|frame| frame.gcd.as_ref())
+      .map(|gcd| gcd.delay_time as u64 * 10)
+      .sum();
+    GifInfo {
+      width: gif.lsd.width,
+      height: gif.lsd.height,
+      frame_count: gif.frames.len() as u32,
+      total_duration: u32::try_from(total_duration).unwrap_or(u32::MAX),
+      loop_count: gif.loop_count,
+    }
+  }
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct LogicalScreenDescriptor {
+  pub width: u32,
+  pub height: u32,
+  pub global_color_flag: bool,
+  pub color_resolution: u32,
+  pub sorted_flag: bool,
+  pub global_color_size: u32,
+  pub background_color_index: u32,
+  pub pixel_aspect_ratio: u32,
+}
+
+#[derive(Derivative, Default, Clone)]
+#[derivative(Debug)]
+pub struct Frame {
+  /// Absent when the frame is not preceded by a Graphic Control Extension, e.g. in GIF87a files
+  pub gcd: Option<GraphicsControlExtension>,
+  pub im: ImageDescriptor,
+  pub color_table: Vec<Color>,
+  /// Empty until the frame is decoded when parsed with ParseOptions.lazy
+  pub index_stream: Vec<u8>,
+  /// Present when the frame is drawn by a Plain Text Extension instead of an image
+  pub plain_text: Option<PlainTextExtension>,
+  /// Generated when decoding the frame, decodeFrame resumes compositing from it instead of the first frame, can be disabled using DecoderOptions.storeCache and bounded with DecoderOptions.cacheBudget
+  #[derivative(Debug = "ignore")]
+  pub cached_frame: Option<Vec<u8>>,
+  /// Generated with the cache when the frame uses disposal method 3, the canvas it was drawn onto and restores to
+  #[derivative(Debug = "ignore")]
+  pub previous_pixels: Option<Vec<u8>>,
+  pub(crate) lzw_minimum_code_size: u8,
+  /// Ranges of the LZW compressed data sub-blocks in the file
+  pub(crate) data_ranges: Vec<Range<usize>>,
+}
+
+impl Frame {
+  pub(crate) fn disposal_method(&self) -> u32 {
+    match &self.gcd {
+      Some(gcd) => gcd.disposal_method,
+      None => DISPOSAL_UNSPECIFIED,
+    }
+  }
+
+  /// Bytes of LZW compressed image data in the file
+  pub(crate) fn compressed_size(&self) -> usize {
+    self.data_ranges.iter().map(|range| range.len()).sum()
+  }
+
+  pub(crate) fn is_transparent_index(&self, color_index: u8) -> bool {
+    match &self.gcd {
+      Some(gcd) => gcd.transparent_color_flag && color_index as u32 == gcd.transparent_color_index,
+      None => false,
+    }
+  }
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ImageDescriptor {
+  pub left: u32,
+  pub top: u32,
+  pub width: u32,
+  pub height: u32,
+  pub interlace_flag: bool,
+  pub sort_flag: bool,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct GraphicsControlExtension {
+  pub disposal_method: u32,
+  pub user_input_flag: bool,
+  pub transparent_color_flag: bool,
+  pub delay_time: u32,
+  pub transparent_color_index: u32,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct Color {
+  pub red: u32,
+  pub green: u32,
+  pub blue: u32,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct PlainTextExtension {
+  pub text_grid_left: u32,
+  pub text_grid_top: u32,
+  pub text_grid_width: u32,
+  pub text_grid_height: u32,
+  pub character_cell_width: u32,
+  pub character_cell_height: u32,
+  pub text_foreground_color_index: u32,
+  pub text_background_color_index: u32,
+  pub text: String,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct ApplicationExtension {
+  /// Byte offset of the extension introducer in the file
+  pub offset: u32,
+  pub identifier: String,
+  pub authentication_code: String,
+  /// Payload of the data sub-blocks, concatenated
+  pub data: Vec<u8>,
+  /// XML of the XMP packet, only present for `XMP DataXMP` extensions
+  pub xmp: Option<String>,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct UnknownExtension {
+  /// Byte offset of the extension introducer in the file
+  pub offset: u32,
+  pub label: u32,
+  /// Payload of the data sub-blocks, concatenated
+  pub data: Vec<u8>,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct CommentExtension {
+  /// Index of the frame the comment precedes, equal to the frame count when it comes after the last frame
+  pub frame_index: u32,
+  pub data: Vec<u8>,
+  /// `data` decoded as UTF-8, falling back to Latin-1 when it is not valid UTF-8
+  pub text: String,
+}
@@ -0,0 +1,186 @@
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::Color;
+
+// XYZ (D50) to linear sRGB, Bradford adapted
+const XYZ_D50_TO_SRGB: [[f64; 3]; 3] = [
+  [3.1338561, -1.6168667, -0.4906146],
+  [-0.9787684, 1.9161415, 0.0334540],
+  [0.0719453, -0.2289914, 1.4052427],
+];
+
+#[derive(Debug, Clone)]
+enum ToneCurve {
+  Identity,
+  Gamma(f64),
+  Table(Vec<f64>),
+  Parametric(u16, [f64; 7]),
+}
+
+impl ToneCurve {
+  fn parse(profile: &[u8], offset: usize, size: usize) -> Option<ToneCurve> {
+    let tag = profile.get(offset..offset.checked_add(size)?)?;
+    match tag.get(0..4)? {
+      b"curv" => {
+        let count = BigEndian::read_u32(tag.get(8..12)?) as usize;
+        match count {
+          0 => Some(ToneCurve::Identity),
+          1 => Some(ToneCurve::Gamma(
+            BigEndian::read_u16(tag.get(12..14)?) as f64 / 256.0,
+          )),
+          _ => {
+            let entries = tag.get(12..12 + count * 2)?;
+            Some(ToneCurve::Table(
+              entries
+                .chunks_exact(2)
+                .map(|entry| BigEndian::read_u16(entry) as f64 / 65535.0)
+                .collect(),
+            ))
+          }
+        }
+      }
+      b"para" => {
+        let function_type = BigEndian::read_u16(tag.get(8..10)?);
+        let parameter_count = match function_type {
+          0 => 1,
+          1 => 3,
+          2 => 4,
+          3 => 5,
+          4 => 7,
+          _ => return None,
+        };
+        let mut parameters = [0.0; 7];
+        for (i, parameter) in parameters.iter_mut().enumerate().take(parameter_count) {
+          *parameter = read_s15_fixed16(tag.get(12 + i * 4..16 + i * 4)?);
+        }
+        Some(ToneCurve::Parametric(function_type, parameters))
+      }
+      _ => None,
+    }
+  }
+
+  fn apply(&self, x: f64) -> f64 {
+    match self {
+      ToneCurve::Identity => x,
+      ToneCurve::Gamma(gamma) => x.powf(*gamma),
+      ToneCurve::Table(entries) => {
+        let position = x * (entries.len() - 1) as f64;
+        let index = position.floor() as usize;
+        if index + 1 >= entries.len() {
+          return entries[entries.len() - 1];
+        }
+        let fraction = position - index as f64;
+        entries[index] + (entries[index + 1] - entries[index]) * fraction
+      }
+      ToneCurve::Parametric(function_type, [g, a, b, c, d, e, f]) => match function_type {
+        0 => x.powf(*g),
+        1 if x >= -b / a => (a * x + b).powf(*g),
+        1 => 0.0,
+        2 if x >= -b / a => (a * x + b).powf(*g) + c,
+        2 => *c,
+        3 if x >= *d => (a * x + b).powf(*g),
+        3 => c * x,
+        _ if x >= *d => (a * x + b).powf(*g) + e,
+        _ => c * x + f,
+      },
+    }
+  }
+}
+
+/// A matrix/TRC based RGB ICC profile
+#[derive(Debug, Clone)]
+pub struct IccProfile {
+  // Columns are the red, green and blue colorants in XYZ (D50)
+  colorants: [[f64; 3]; 3],
+  tone_curves: [ToneCurve; 3],
+}
+
+impl IccProfile {
+  /// Parses the profile, returns `None` if it is not a matrix/TRC RGB profile
+  pub fn parse(profile: &[u8]) -> Option<IccProfile> {
+    if profile.get(16..20)? != b"RGB " || profile.get(36..40)? != b"acsp" {
+      return None;
+    }
+
+    let tag_count = BigEndian::read_u32(profile.get(128..132)?) as usize;
+    let mut colorants: [Option<[f64; 3]>; 3] = [None, None, None];
+    let mut tone_curves: [Option<ToneCurve>; 3] = [None, None, None];
+    for i in 0..tag_count {
+      let entry = profile.get(132 + i * 12..144 + i * 12)?;
+      let offset = BigEndian::read_u32(&entry[4..8]) as usize;
+      let size = BigEndian::read_u32(&entry[8..12]) as usize;
+      match &entry[0..4] {
+        b"rXYZ" => colorants[0] = Some(read_xyz(profile, offset)?),
+        b"gXYZ" => colorants[1] = Some(read_xyz(profile, offset)?),
+        b"bXYZ" => colorants[2] = Some(read_xyz(profile, offset)?),
+        b"rTRC" => tone_curves[0] = Some(ToneCurve::parse(profile, offset, size)?),
+        b"gTRC" => tone_curves[1] = Some(ToneCurve::parse(profile, offset, size)?),
+        b"bTRC" => tone_curves[2] = Some(ToneCurve::parse(profile, offset, size)?),
+        _ => {}
+      }
+    }
+
+    let [red, green, blue] = colorants;
+    let (red, green, blue) = (red?, green?, blue?);
+    let [red_curve, green_curve, blue_curve] = tone_curves;
+    Some(IccProfile {
+      colorants: [
+        [red[0], green[0], blue[0]],
+        [red[1], green[1], blue[1]],
+        [red[2], green[2], blue[2]],
+      ],
+      tone_curves: [red_curve?, green_curve?, blue_curve?],
+    })
+  }
+
+  /// Converts a colour from the profile's colour space into sRGB
+  pub fn to_srgb(&self, color: &Color) -> Color {
+    let channels = [color.red, color.green, color.blue];
+    let mut linear = [0.0; 3];
+    for i in 0..3 {
+      linear[i] = self.tone_curves[i].apply(channels[i].min(255) as f64 / 255.0);
+    }
+
+    let xyz = multiply(&self.colorants, &linear);
+    let srgb = multiply(&XYZ_D50_TO_SRGB, &xyz);
+    Color {
+      red: encode_srgb(srgb[0]),
+      green: encode_srgb(srgb[1]),
+      blue: encode_srgb(srgb[2]),
+    }
+  }
+}
+
+fn multiply(matrix: &[[f64; 3]; 3], vector: &[f64; 3]) -> [f64; 3] {
+  let mut result = [0.0; 3];
+  for (i, row) in matrix.iter().enumerate() {
+    result[i] = row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2];
+  }
+  result
+}
+
+fn encode_srgb(linear: f64) -> u32 {
+  let linear = linear.clamp(0.0, 1.0);
+  let encoded = if linear <= 0.0031308 {
+    linear * 12.92
+  } else {
+    1.055 * linear.powf(1.0 / 2.4) - 0.055
+  };
+  (encoded * 255.0).round() as u32
+}
+
+fn read_xyz(profile: &[u8], offset: usize) -> Option<[f64; 3]> {
+  let tag = profile.get(offset..offset.checked_add(20)?)?;
+  if &tag[0..4] != b"XYZ " {
+    return None;
+  }
+  Some([
+    read_s15_fixed16(&tag[8..12]),
+    read_s15_fixed16(&tag[12..16]),
+    read_s15_fixed16(&tag[16..20]),
+  ])
+}
+
+fn read_s15_fixed16(bytes: &[u8]) -> f64 {
+  BigEndian::read_i32(bytes) as f64 / 65536.0
+}
@@ -0,0 +1,109 @@
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::error::{Error, Result};
+
+/// Bounds checked while parsing, before anything is allocated for the image data, every limit is unset by default
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct DecoderLimits {
+  /// Maximum width of the logical screen and of every frame
+  pub max_width: Option<u32>,
+  /// Maximum height of the logical screen and of every frame
+  pub max_height: Option<u32>,
+  /// Maximum number of pixels (width * height) of the logical screen and of every frame
+  pub max_pixels: Option<u32>,
+  /// Maximum number of frames, plain text frames included
+  pub max_frames: Option<u32>,
+  /// Maximum number of bytes of the decompressed index streams and of the RGBA frames decodeFrames composites, one per frame
+  pub max_decoded_bytes: Option<u32>,
+  /// Maximum number of pixels decompressed from each byte of LZW data in a frame
+  pub max_compression_ratio: Option<f64>,
+}
+
+impl DecoderLimits {
+  pub(crate) fn check_dimensions(
+    &self,
+    width: u32,
+    height: u32,
+    offset: usize,
+    frame_index: Option<usize>,
+  ) -> Result<()> {
+    if exceeds(width as u64, self.max_width) {
+      return Err(Error::LimitsExceeded {
+        limit: "max_width",
+        offset,
+        frame_index,
+      });
+    }
+    if exceeds(height as u64, self.max_height) {
+      return Err(Error::LimitsExceeded {
+        limit: "max_height",
+        offset,
+        frame_index,
+      });
+    }
+    if exceeds(width as u64 * height as u64, self.max_pixels) {
+      return Err(Error::LimitsExceeded {
+        limit: "max_pixels",
+        offset,
+        frame_index,
+      });
+    }
+    Ok(())
+  }
+
+  pub(crate) fn check_frame_count(&self, frame_count: usize, offset: usize) -> Result<()> {
+    if exceeds(frame_count as u64, self.max_frames) {
+      return Err(Error::LimitsExceeded {
+        limit: "max_frames",
+        offset,
+        frame_index: Some(frame_count - 1),
+      });
+    }
+    Ok(())
+  }
+
+  pub(crate) fn check_decoded_bytes(
+    &self,
+    decoded_bytes: u64,
+    offset: usize,
+    frame_index: Option<usize>,
+  ) -> Result<()> {
+    if exceeds(decoded_bytes, self.max_decoded_bytes) {
+      return Err(Error::LimitsExceeded {
+        limit: "max_decoded_bytes",
+        offset,
+        frame_index,
+      });
+    }
+    Ok(())
+  }
+
+  pub(crate) fn check_compression_ratio(
+    &self,
+    pixels: u64,
+    compressed_bytes: usize,
+    offset: usize,
+    frame_index: usize,
+  ) -> Result<()> {
+    if let Some(max_compression_ratio) = self.max_compression_ratio {
+      // Frames without any data are only allowed when they have no pixels either
+      if pixels as f64 > max_compression_ratio * compressed_bytes as f64 {
+        return Err(Error::LimitsExceeded {
+          limit: "max_compression_ratio",
+          offset,
+          frame_index: Some(frame_index),
+        });
+      }
+    }
+    Ok(())
+  }
+}
+
+fn exceeds(value: u64, limit: Option<u32>) -> bool {
+  match limit {
+    Some(limit) => value > limit as u64,
+    None => false,
+  }
+}
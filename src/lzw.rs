@@ -0,0 +1,227 @@
+use std::ops::{IndexMut, Range};
+
+use crate::control::DecodeControl;
+use crate::decoder::{shl_or, shr_or};
+use crate::error::{Error, Result};
+use crate::gif::Frame;
+
+const MAX_STACK_SIZE: u16 = 4096;
+/// Largest LZW minimum code size whose clear and end of information codes fit in the code table
+pub(crate) const MAX_LZW_MINIMUM_CODE_SIZE: u8 = 11;
+/// Number of pixels decompressed between checks of the cancellation token
+const CANCELLATION_INTERVAL: usize = 0x10000;
+
+/// Decompresses the data sub-blocks of the frame into its index stream, deinterlaced
+pub(crate) fn decode_index_stream(
+  frame: &Frame,
+  contents: &[u8],
+  frame_index: usize,
+  control: &DecodeControl,
+) -> Result<Vec<u8>> {
+  let npix = (frame.im.width * frame.im.height) as usize;
+  let mut index_stream = decode(
+    contents,
+    &frame.data_ranges,
+    frame.lzw_minimum_code_size,
+    npix,
+    frame_index,
+    control,
+  )?;
+  index_stream.resize(npix, 0);
+  if frame.im.interlace_flag && frame.im.width > 0 {
+    index_stream = deinterlace(&mut index_stream, frame.im.width as usize);
+  }
+  Ok(index_stream)
+}
+
+/// Decompresses the index streams of all the frames on the rayon thread pool
+#[cfg(feature = "parallel")]
+pub(crate) fn decode_index_streams(
+  frames: &mut [Frame],
+  contents: &[u8],
+  control: &DecodeControl,
+) -> Result<()> {
+  use rayon::prelude::*;
+
+  let index_streams: Vec<Result<Vec<u8>>> = frames
+    .par_iter()
+    .enumerate()
+    .map(|(frame_index, frame)| decode_index_stream(frame, contents, frame_index, control))
+    .collect();
+  // Report the error of the first corrupted frame, like the sequential decoder
+  for (frame, index_stream) in frames.iter_mut().zip(index_streams) {
+    if !frame.data_ranges.is_empty() {
+      frame.index_stream = index_stream?;
+    }
+  }
+  Ok(())
+}
+
+/// Decompresses the index streams of all the frames, sequentially without the `parallel` feature
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn decode_index_streams(
+  frames: &mut [Frame],
+  contents: &[u8],
+  control: &DecodeControl,
+) -> Result<()> {
+  for (frame_index, frame) in frames.iter_mut().enumerate() {
+    if !frame.data_ranges.is_empty() {
+      frame.index_stream = decode_index_stream(frame, contents, frame_index, control)?;
+    }
+  }
+  Ok(())
+}
+
+fn decode(
+  contents: &[u8],
+  data_ranges: &[Range<usize>],
+  lzw_minimum_code_size: u8,
+  npix: usize,
+  frame_index: usize,
+  control: &DecodeControl,
+) -> Result<Vec<u8>> {
+  let null_code: i32 = -1;
+
+  let clear_code = shl_or(1, lzw_minimum_code_size as usize, 0);
+  let eoi_code = clear_code + 1;
+  let mut available = clear_code + 2;
+  let mut old_code = null_code;
+  let mut code_size: usize = (lzw_minimum_code_size + 1) as usize;
+  let mut code_mask = shl_or(1, code_size, 0) - 1;
+
+  let mut prefix: Vec<u16> = vec![0; MAX_STACK_SIZE as usize]; // No need to fill with 0 (already filled)
+  let mut suffix: Vec<u8> = vec![0; MAX_STACK_SIZE as usize];
+  for code in 0..clear_code {
+    *suffix.index_mut(code as usize) = code as u8;
+  }
+
+  let mut pixel_stack: Vec<u8> = vec![0; (MAX_STACK_SIZE + 1) as usize];
+  let mut top = 0;
+
+  let mut index_stream: Vec<u8> = Vec::with_capacity(npix);
+
+  let mut first: u8 = 0;
+  let mut datum: u32 = 0;
+  let mut bits: usize = 0;
+  // Data sub-block being read and the offset of the next byte in it
+  let mut ri = 0;
+  let mut offset = data_ranges.first().map_or(0, |range| range.start);
+
+  let mut n = 0;
+  while n < npix {
+    if n % CANCELLATION_INTERVAL == 0 {
+      control.check_cancelled()?;
+    }
+    if top == 0 {
+      if bits < code_size {
+        if offset == data_ranges.get(ri).map_or(offset, |range| range.end) {
+          ri += 1;
+          offset = data_ranges.get(ri).map_or(offset, |range| range.start);
+        }
+        if ri >= data_ranges.len() {
+          break;
+        }
+        datum += shl_or(contents[offset] as u32 & 0xFF, bits, 0);
+        bits += 8;
+        offset += 1;
+        continue;
+      }
+      let mut code = datum & code_mask;
+      datum = shr_or(datum, code_size, 0);
+      bits -= code_size;
+      if code == eoi_code {
+        break;
+      }
+      if code > available {
+        return Err(Error::InvalidLzwCode {
+          code,
+          offset,
+          frame_index,
+        });
+      }
+      if code == clear_code {
+        code_size = (lzw_minimum_code_size + 1) as usize;
+        code_mask = shl_or(1, code_size, 0) - 1;
+        available = clear_code + 2;
+        old_code = null_code;
+        continue;
+      }
+      if old_code == null_code {
+        // The first code after a clear code must be in the initial code table
+        if code >= clear_code {
+          return Err(Error::InvalidLzwCode {
+            code,
+            offset,
+            frame_index,
+          });
+        }
+        index_stream.push(suffix[code as usize]);
+        old_code = code as i32;
+        first = code as u8;
+        continue;
+      }
+      let in_code = code;
+      if code == available {
+        *pixel_stack.index_mut(top) = first;
+        top += 1;
+        code = old_code as u32;
+      }
+      while code > clear_code {
+        if top >= MAX_STACK_SIZE as usize {
+          return Err(Error::InvalidLzwCode {
+            code,
+            offset,
+            frame_index,
+          });
+        }
+        *pixel_stack.index_mut(top) = suffix[code as usize];
+        top += 1;
+        code = prefix[code as usize] as u32;
+      }
+      first = suffix[code as usize];
+
+      *pixel_stack.index_mut(top) = first;
+      top += 1;
+
+      if available < MAX_STACK_SIZE as u32 {
+        *prefix.index_mut(available as usize) = old_code as u16;
+        *suffix.index_mut(available as usize) = first;
+        available += 1;
+        if (available & code_mask) == 0 && available < MAX_STACK_SIZE as u32 {
+          code_size += 1;
+          code_mask += available;
+        }
+      }
+      old_code = in_code as i32;
+    }
+    top -= 1;
+    index_stream.push(pixel_stack[top]);
+    n += 1;
+  }
+  Ok(index_stream)
+}
+
+// deinterlace function from https://github.com/matt-way/gifuct-js/blob/master/src/deinterlace.js
+fn deinterlace(index_stream: &mut [u8], width: usize) -> Vec<u8> {
+  let mut new_index_stream = vec![0; index_stream.len()];
+  let rows = index_stream.len() / width;
+
+  // See appendix E.
+  let offsets = [0, 4, 2, 1];
+  let steps = [8, 8, 4, 2];
+
+  let mut from_row = 0;
+  for pass in 0..4 {
+    let mut to_row = offsets[pass];
+    while to_row < rows {
+      let from_pixels = &index_stream[from_row * width..(from_row + 1) * width];
+      new_index_stream.splice(
+        (to_row * width)..(to_row * width) + width,
+        from_pixels.to_vec(),
+      );
+      from_row += 1;
+      to_row += steps[pass];
+    }
+  }
+  new_index_stream
+}
@@ -0,0 +1,981 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use napi::bindgen_prelude::{AsyncTask, Buffer, ClassInstance, FromNapiValue, ToNapiValue};
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{sys, CallContext, Env, JsError, JsFunction, JsObject, JsSymbol, JsUnknown, Ref, Task};
+use napi_derive::{js_function, napi};
+
+use crate::compositor::Compositor;
+
+use crate::{
+  ApplicationExtension, CacheStats, Color, CommentExtension, DecoderOptions, GifInfo,
+  GraphicsControlExtension, ImageDescriptor, LogicalScreenDescriptor, ParseOptions,
+  PlainTextExtension, UnknownExtension,
+};
+
+/// Thrown errors are JS errors with the stable `crate::Error::code` as their `code` property, and the `offset`
+/// and `frameIndex` where decoding failed when they are known
+fn to_napi_error(env: Env, error: crate::Error) -> napi::Error {
+  match create_js_error(env, &error) {
+    Ok(js_error) => napi::Error::from(js_error),
+    Err(create_error) => create_error,
+  }
+}
+
+/// napi::Error only carries a code and a message, so the error is created in JS first to add the properties
+fn create_js_error(env: Env, error: &crate::Error) -> napi::Result<JsUnknown> {
+  let js_error = JsError::from(napi::Error::new(error.code(), error.to_string())).into_unknown(env);
+  let mut js_error = js_error.coerce_to_object()?;
+  if let Some(offset) = error.offset() {
+    js_error.set_named_property("offset", offset as u32)?;
+  }
+  if let Some(frame_index) = error.frame_index() {
+    js_error.set_named_property("frameIndex", frame_index as u32)?;
+  }
+  Ok(js_error.into_unknown())
+}
+
+/// A Gif shared by its wrapper, its frame iterators and the tasks of its async methods
+struct SharedGif {
+  gif: Mutex<crate::Gif>,
+  // Published whenever the Gif is unlocked, so cacheStats does not wait for a running decode
+  cache_stats: Mutex<CacheStats>,
+}
+
+impl SharedGif {
+  fn new(gif: crate::Gif) -> SharedGif {
+    SharedGif {
+      cache_stats: Mutex::new(gif.cache_stats()),
+      gif: Mutex::new(gif),
+    }
+  }
+
+  fn cache_stats(&self) -> CacheStats {
+    self
+      .cache_stats
+      .lock()
+      .unwrap_or_else(|error| error.into_inner())
+      .clone()
+  }
+}
+
+/// The locked Gif, publishing its cache stats once unlocked
+struct LockedGif<'a> {
+  gif: MutexGuard<'a, crate::Gif>,
+  cache_stats: &'a Mutex<CacheStats>,
+}
+
+impl Deref for LockedGif<'_> {
+  type Target = crate::Gif;
+
+  fn deref(&self) -> &crate::Gif {
+    &self.gif
+  }
+}
+
+impl DerefMut for LockedGif<'_> {
+  fn deref_mut(&mut self) -> &mut crate::Gif {
+    &mut self.gif
+  }
+}
+
+impl Drop for LockedGif<'_> {
+  fn drop(&mut self) {
+    *self
+      .cache_stats
+      .lock()
+      .unwrap_or_else(|error| error.into_inner()) = self.gif.cache_stats();
+  }
+}
+
+// A panic while decoding can leave the cache and checkpoints partly updated, so they are dropped before
+// the Gif is used again, the index streams are only ever replaced whole
+fn lock(shared: &SharedGif) -> LockedGif<'_> {
+  let gif = shared.gif.lock().unwrap_or_else(|error| {
+    let mut gif = error.into_inner();
+    gif.clear_cache();
+    shared.gif.clear_poison();
+    gif
+  });
+  LockedGif {
+    gif,
+    cache_stats: &shared.cache_stats,
+  }
+}
+
+/// What the getters of Gif return, copied when parsing so they never wait for the async methods decoding the Gif
+struct Metadata {
+  version: String,
+  lsd: LogicalScreenDescriptor,
+  global_table: Vec<Color>,
+  frames: Vec<Frame>,
+  loop_count: Option<u32>,
+  buffer_size: Option<u32>,
+  comments: Vec<CommentExtension>,
+  application_extensions: Vec<ApplicationExtension>,
+  unknown_extensions: Vec<UnknownExtension>,
+  orphaned_gcds: Vec<GraphicsControlExtension>,
+  icc_profile: Option<Vec<u8>>,
+}
+
+impl From<&crate::Gif> for Metadata {
+  fn from(gif: &crate::Gif) -> Metadata {
+    Metadata {
+      version: gif.version.clone(),
+      lsd: gif.lsd.clone(),
+      global_table: gif.global_table.clone(),
+      frames: gif.frames.iter().map(Frame::from).collect(),
+      loop_count: gif.loop_count,
+      buffer_size: gif.buffer_size,
+      comments: gif.comments.clone(),
+      application_extensions: gif.application_extensions.clone(),
+      unknown_extensions: gif.unknown_extensions.clone(),
+      orphaned_gcds: gif.orphaned_gcds.clone(),
+      icc_profile: gif.icc_profile.clone(),
+    }
+  }
+}
+
+/// Fails with ERR_GIF_FRAME_OUT_OF_BOUNDS when the frame does not exist
+fn frame(gif: &crate::Gif, frame_index: u32) -> crate::Result<&crate::Frame> {
+  gif
+    .frames
+    .get(frame_index as usize)
+    .ok_or(crate::Error::FrameOutOfBounds {
+      frame_index: frame_index as usize,
+      frame_count: gif.frames.len(),
+    })
+}
+
+/// DecoderOptions.background, `{ kind: 'custom', color: [red, green, blue, alpha] }` for a custom colour
+#[napi(object)]
+pub struct Background {
+  /// `transparent` | `logicalScreen` | `custom`, logicalScreen is the opaque colour at lsd.backgroundColorIndex of the global colour table
+  #[napi(ts_type = "'transparent' | 'logicalScreen' | 'custom'")]
+  pub kind: String,
+  /// RGBA of `custom`, every channel from 0 to 255
+  #[napi(ts_type = "[number, number, number, number]")]
+  pub color: Option<Vec<u8>>,
+}
+
+impl FromNapiValue for crate::Background {
+  unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> napi::Result<Self> {
+    let background = Background::from_napi_value(env, napi_val)?;
+    match (background.kind.as_str(), background.color.as_deref()) {
+      ("transparent", _) => Ok(crate::Background::Transparent),
+      ("logicalScreen", _) => Ok(crate::Background::LogicalScreen),
+      ("custom", Some(&[red, green, blue, alpha])) => {
+        Ok(crate::Background::Custom([red, green, blue, alpha]))
+      }
+      ("custom", _) => Err(napi::Error::new(
+        napi::Status::InvalidArg,
+        "A custom background needs a color of 4 channels".to_string(),
+      )),
+      (kind, _) => Err(napi::Error::new(
+        napi::Status::InvalidArg,
+        format!("Unknown background kind {}", kind),
+      )),
+    }
+  }
+}
+
+impl ToNapiValue for crate::Background {
+  unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+    let background = match val {
+      crate::Background::Transparent => Background {
+        kind: "transparent".to_string(),
+        color: None,
+      },
+      crate::Background::LogicalScreen => Background {
+        kind: "logicalScreen".to_string(),
+        color: None,
+      },
+      crate::Background::Custom(color) => Background {
+        kind: "custom".to_string(),
+        color: Some(color.to_vec()),
+      },
+    };
+    Background::to_napi_value(env, background)
+  }
+}
+
+/// Lets a lazy Gif read the image data straight from the JS Buffer it was decoded from
+struct BufferContents(Buffer);
+
+impl AsRef<[u8]> for BufferContents {
+  fn as_ref(&self) -> &[u8] {
+    &self.0
+  }
+}
+
+// SAFETY: The buffer is never written to from Rust. Its last clone can be dropped on a libuv
+// worker, e.g. in the compute of a decodeBufferAsync that is not lazy or fails, which is sound
+// because napi's Buffer::drop only releases the JS reference directly on threads that can access
+// the env, and otherwise hands it to the threadsafe function napi registers for its custom GC
+// (CUSTOM_GC_TSFN through napi_call_threadsafe_function), which releases it on the JS thread.
+// JS can still write to it, so it must not be modified while decodeBufferAsync reads it on the
+// threadpool, nor while a lazy Gif decoded from it is used, as documented on decodeBuffer and
+// decodeBufferAsync
+unsafe impl Sync for BufferContents {}
+
+/// Progress callback and cancellation of the async methods
+#[napi(object, object_to_js = false)]
+pub struct DecodeControl {
+  /// Called on the event loop after every frame, the last calls may arrive after the promise settles
+  #[napi(ts_type = "(progress: Progress) => void")]
+  pub on_progress: Option<JsFunction>,
+  /// Rejects with ERR_GIF_CANCELLED once aborted, checked between frames and periodically while decompressing
+  #[napi(ts_type = "AbortSignal")]
+  pub signal: Option<JsObject>,
+}
+
+/// The listener cancelling a task once its DecodeControl.signal is aborted, removed when the task settles
+struct AbortListener {
+  signal: Ref<()>,
+  on_abort: Ref<()>,
+}
+
+impl AbortListener {
+  fn remove(listener: Option<Self>, env: Env) -> napi::Result<()> {
+    let mut listener = match listener {
+      Some(listener) => listener,
+      None => return Ok(()),
+    };
+    let values = env
+      .get_reference_value::<JsObject>(&listener.signal)
+      .and_then(|signal| {
+        Ok((
+          signal,
+          env.get_reference_value::<JsFunction>(&listener.on_abort)?,
+        ))
+      });
+    // The references are released even when the values can not be read
+    listener.signal.unref(env)?;
+    listener.on_abort.unref(env)?;
+    let (signal, on_abort) = values?;
+    let remove_event_listener: JsFunction = signal.get_named_property("removeEventListener")?;
+    remove_event_listener.call::<JsUnknown>(
+      Some(&signal),
+      &[
+        env.create_string("abort")?.into_unknown(),
+        on_abort.into_unknown(),
+      ],
+    )?;
+    Ok(())
+  }
+}
+
+impl DecodeControl {
+  fn into_control(
+    control: Option<Self>,
+    env: Env,
+  ) -> napi::Result<(crate::DecodeControl, Option<AbortListener>)> {
+    let DecodeControl {
+      on_progress,
+      signal,
+    } = match control {
+      Some(control) => control,
+      None => return Ok((crate::DecodeControl::default(), None)),
+    };
+    let mut abort_listener = None;
+    let mut decode_control = crate::DecodeControl::default();
+    if let Some(on_progress) = on_progress {
+      let on_progress: ThreadsafeFunction<crate::Progress, ErrorStrategy::Fatal> = on_progress
+        .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<crate::Progress>| {
+          Ok(vec![ctx.value])
+        })?;
+      decode_control.on_progress = Some(Arc::new(move |progress| {
+        on_progress.call(progress, ThreadsafeFunctionCallMode::NonBlocking);
+      }));
+    }
+    if let Some(signal) = signal {
+      let cancellation_token = crate::CancellationToken::new();
+      if signal.get_named_property::<bool>("aborted")? {
+        cancellation_token.cancel();
+      } else {
+        let on_abort_token = cancellation_token.clone();
+        let on_abort = env.create_function_from_closure("onAbort", move |_| {
+          on_abort_token.cancel();
+          Ok(())
+        })?;
+        let mut options = env.create_object()?;
+        options.set_named_property("once", true)?;
+        let arguments = [
+          env.create_string("abort")?.into_unknown(),
+          on_abort.into_unknown(),
+          options.into_unknown(),
+        ];
+        let add_event_listener: JsFunction = signal.get_named_property("addEventListener")?;
+        add_event_listener.call::<JsUnknown>(Some(&signal), &arguments)?;
+        abort_listener = Some(AbortListener {
+          signal: env.create_reference(&signal)?,
+          on_abort: env.create_reference(&arguments[1])?,
+        });
+      }
+      decode_control.cancellation_token = Some(cancellation_token);
+    }
+    Ok((decode_control, abort_listener))
+  }
+}
+
+pub struct DecodePathTask {
+  file_path: String,
+  parse_options: ParseOptions,
+  control: crate::DecodeControl,
+  abort_listener: Option<AbortListener>,
+}
+
+#[napi]
+impl Task for DecodePathTask {
+  type Output = crate::Result<crate::Gif>;
+  type JsValue = Gif;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(crate::Decoder::decode_path_with_control(
+      &self.file_path,
+      &self.parse_options,
+      &self.control,
+    ))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    match output {
+      Ok(gif) => Ok(Gif::from(gif)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  fn finally(&mut self, env: Env) -> napi::Result<()> {
+    AbortListener::remove(self.abort_listener.take(), env)
+  }
+}
+
+pub struct DecodeBufferTask {
+  buffer: Buffer,
+  parse_options: ParseOptions,
+  control: crate::DecodeControl,
+  abort_listener: Option<AbortListener>,
+}
+
+#[napi]
+impl Task for DecodeBufferTask {
+  type Output = crate::Result<crate::Gif>;
+  type JsValue = Gif;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(crate::Decoder::decode_owned_with_control(
+      BufferContents(self.buffer.clone()),
+      &self.parse_options,
+      &self.control,
+    ))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    match output {
+      Ok(gif) => Ok(Gif::from(gif)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  fn finally(&mut self, env: Env) -> napi::Result<()> {
+    AbortListener::remove(self.abort_listener.take(), env)
+  }
+}
+
+pub struct DecodeFramesTask {
+  gif: Arc<SharedGif>,
+  decoder_options: DecoderOptions,
+  control: crate::DecodeControl,
+  abort_listener: Option<AbortListener>,
+}
+
+#[napi]
+impl Task for DecodeFramesTask {
+  type Output = crate::Result<Vec<Vec<u8>>>;
+  type JsValue = Vec<Buffer>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(lock(&self.gif).decode_frames_with_control(&self.decoder_options, &self.control))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    match output {
+      Ok(buffers) => Ok(buffers.into_iter().map(Buffer::from).collect()),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  fn finally(&mut self, env: Env) -> napi::Result<()> {
+    AbortListener::remove(self.abort_listener.take(), env)
+  }
+}
+
+pub struct DecodeFrameTask {
+  gif: Arc<SharedGif>,
+  frame_index: u32,
+  decoder_options: DecoderOptions,
+}
+
+#[napi]
+impl Task for DecodeFrameTask {
+  type Output = crate::Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(lock(&self.gif).decode_frame(self.frame_index as usize, &self.decoder_options))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    match output {
+      Ok(buffer) => Ok(Buffer::from(buffer)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+}
+
+#[napi]
+pub struct Gif {
+  metadata: Metadata,
+  gif: Arc<SharedGif>,
+}
+
+impl From<crate::Gif> for Gif {
+  fn from(gif: crate::Gif) -> Gif {
+    Gif {
+      metadata: Metadata::from(&gif),
+      gif: Arc::new(SharedGif::new(gif)),
+    }
+  }
+}
+
+impl Gif {
+  fn gif(&self) -> LockedGif<'_> {
+    lock(&self.gif)
+  }
+}
+
+#[napi]
+impl Gif {
+  #[napi(getter)]
+  pub fn version(&self) -> String {
+    self.metadata.version.clone()
+  }
+
+  #[napi(getter)]
+  pub fn lsd(&self) -> LogicalScreenDescriptor {
+    self.metadata.lsd.clone()
+  }
+
+  #[napi(getter)]
+  pub fn global_table(&self) -> Vec<Color> {
+    self.metadata.global_table.clone()
+  }
+
+  #[napi(getter)]
+  pub fn frames(&self) -> Vec<Frame> {
+    self.metadata.frames.clone()
+  }
+
+  /// Number of times the animation repeats, taken from the NETSCAPE2.0 / ANIMEXTS1.0 extension, `0` means loop forever
+  #[napi(getter)]
+  pub fn loop_count(&self) -> Option<u32> {
+    self.metadata.loop_count
+  }
+
+  /// Suggested buffer size in bytes, taken from the NETSCAPE2.0 / ANIMEXTS1.0 extension
+  #[napi(getter)]
+  pub fn buffer_size(&self) -> Option<u32> {
+    self.metadata.buffer_size
+  }
+
+  #[napi(getter)]
+  pub fn comments(&self) -> Vec<CommentExtension> {
+    self.metadata.comments.clone()
+  }
+
+  #[napi(getter)]
+  pub fn application_extensions(&self) -> Vec<ApplicationExtension> {
+    self.metadata.application_extensions.clone()
+  }
+
+  #[napi(getter)]
+  pub fn unknown_extensions(&self) -> Vec<UnknownExtension> {
+    self.metadata.unknown_extensions.clone()
+  }
+
+  /// Graphic Control Extensions that were not followed by an image or plain text
+  #[napi(getter)]
+  pub fn orphaned_gcds(&self) -> Vec<GraphicsControlExtension> {
+    self.metadata.orphaned_gcds.clone()
+  }
+
+  /// ICC colour profile, taken from the ICCRGBG1 extension
+  #[napi(getter)]
+  pub fn icc_profile(&self) -> Option<Vec<u8>> {
+    self.metadata.icc_profile.clone()
+  }
+
+  #[napi]
+  pub fn decode_frames(
+    &mut self,
+    env: Env,
+    decoder_options: DecoderOptions,
+  ) -> napi::Result<Vec<Buffer>> {
+    match self.gif().decode_frames(&decoder_options) {
+      Ok(buffers) => Ok(buffers.into_iter().map(Buffer::from).collect()),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Every frame as the area it draws, where to draw it and how to dispose of it, for compositing the frames elsewhere,
+  /// drawing `pixels` where they are opaque onto the canvas then applying `disposal` composites the same frames as decodeFrames
+  #[napi]
+  pub fn decode_deltas(
+    &mut self,
+    env: Env,
+    decoder_options: DecoderOptions,
+  ) -> napi::Result<Vec<FrameDelta>> {
+    match self.gif().decode_deltas(&decoder_options) {
+      Ok(deltas) => Ok(deltas.into_iter().map(FrameDelta::from).collect()),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  #[napi]
+  pub fn decode_delta(
+    &mut self,
+    env: Env,
+    frame_index: u32,
+    decoder_options: DecoderOptions,
+  ) -> napi::Result<FrameDelta> {
+    match self
+      .gif()
+      .decode_delta(frame_index as usize, &decoder_options)
+    {
+      Ok(delta) => Ok(FrameDelta::from(delta)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Index stream of the frame, decompressed first when parsed with ParseOptions.lazy
+  #[napi]
+  pub fn index_stream(&mut self, env: Env, frame_index: u32) -> napi::Result<Buffer> {
+    match self.gif().index_stream(frame_index as usize) {
+      Ok(index_stream) => Ok(Buffer::from(index_stream)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Same as decodeFrames, on the libuv threadpool
+  #[napi]
+  pub fn decode_frames_async(
+    &self,
+    env: Env,
+    decoder_options: DecoderOptions,
+    control: Option<DecodeControl>,
+  ) -> napi::Result<AsyncTask<DecodeFramesTask>> {
+    let (control, abort_listener) = DecodeControl::into_control(control, env)?;
+    Ok(AsyncTask::new(DecodeFramesTask {
+      gif: self.gif.clone(),
+      decoder_options,
+      control,
+      abort_listener,
+    }))
+  }
+
+  /// Same as decodeFrame, on the libuv threadpool
+  #[napi]
+  pub fn decode_frame_async(
+    &self,
+    frame_index: u32,
+    decoder_options: DecoderOptions,
+  ) -> AsyncTask<DecodeFrameTask> {
+    AsyncTask::new(DecodeFrameTask {
+      gif: self.gif.clone(),
+      frame_index,
+      decoder_options,
+    })
+  }
+
+  #[napi]
+  pub fn decode_frame(
+    &mut self,
+    env: Env,
+    frame_index: u32,
+    decoder_options: DecoderOptions,
+  ) -> napi::Result<Buffer> {
+    match self
+      .gif()
+      .decode_frame(frame_index as usize, &decoder_options)
+    {
+      Ok(buffer) => Ok(Buffer::from(buffer)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Copy of the frame cached when decoding it, decodeFrame resumes compositing from it instead of the first frame, can be disabled using DecoderOptions.storeCache and bounded with DecoderOptions.cacheBudget
+  #[napi]
+  pub fn cached_frame(&self, env: Env, frame_index: u32) -> napi::Result<Option<Buffer>> {
+    let gif = self.gif();
+    match frame(&gif, frame_index) {
+      Ok(frame) => Ok(frame.cached_frame.clone().map(Buffer::from)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Copy of the canvas the frame was drawn onto and restores to, cached with the frame when it uses disposal method 3
+  #[napi]
+  pub fn previous_pixels(&self, env: Env, frame_index: u32) -> napi::Result<Option<Buffer>> {
+    let gif = self.gif();
+    match frame(&gif, frame_index) {
+      Ok(frame) => Ok(frame.previous_pixels.clone().map(Buffer::from)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Drops every cached frame and checkpoint, the next decodes composite the frames again
+  #[napi]
+  pub fn clear_cache(&mut self) {
+    self.gif().clear_cache()
+  }
+
+  /// Counted once the calls finish, a running async decode is only counted once its promise settles
+  #[napi]
+  pub fn cache_stats(&self) -> CacheStats {
+    self.gif.cache_stats()
+  }
+
+  /// Frames composited the same onto an empty canvas as onto the previous frames, decodeFrame never composites frames before the closest one
+  #[napi]
+  pub fn keyframes(&mut self, decoder_options: DecoderOptions) -> Vec<u32> {
+    self
+      .gif()
+      .keyframes(&decoder_options)
+      .iter()
+      .map(|frame_index| *frame_index as u32)
+      .collect()
+  }
+
+  /// Composites the frames one at a time, only keeping the canvas the next frame is drawn onto, frames are never cached regardless of storeCache
+  #[napi(ts_return_type = "FrameIterator & IterableIterator<Buffer>")]
+  pub fn iter_frames(
+    &self,
+    env: Env,
+    decoder_options: DecoderOptions,
+  ) -> napi::Result<ClassInstance<FrameIterator>> {
+    let compositor = Compositor::without_cache(&decoder_options);
+    let iterator = FrameIterator {
+      gif: self.gif.clone(),
+      compositor,
+    }
+    .into_instance(env)?;
+    // The iterator is its own iterable so it can be used with for...of
+    let symbol_iterator: JsSymbol = env
+      .get_global()?
+      .get_named_property_unchecked::<JsObject>("Symbol")?
+      .get_named_property("iterator")?;
+    iterator.as_object(env).set_property(
+      symbol_iterator,
+      env.create_function("iterator", return_this)?,
+    )?;
+    Ok(iterator)
+  }
+}
+
+#[js_function]
+fn return_this(ctx: CallContext) -> napi::Result<JsObject> {
+  ctx.this()
+}
+
+/// Yields the composited frames of a Gif, created by gif.iterFrames
+#[napi]
+pub struct FrameIterator {
+  gif: Arc<SharedGif>,
+  compositor: Compositor,
+}
+
+/// Result of FrameIterator.next, following the iterator protocol
+#[napi(object)]
+pub struct FrameIteratorResult {
+  pub done: bool,
+  pub value: Option<Buffer>,
+}
+
+#[napi]
+impl FrameIterator {
+  #[napi(ts_return_type = "IteratorResult<Buffer, undefined>")]
+  pub fn next(&mut self, env: Env) -> napi::Result<FrameIteratorResult> {
+    match self
+      .compositor
+      .next(&mut lock(&self.gif), &crate::DecodeControl::default())
+    {
+      Some(Ok(buffer)) => Ok(FrameIteratorResult {
+        done: false,
+        value: Some(Buffer::from(buffer)),
+      }),
+      Some(Err(error)) => Err(to_napi_error(env, error)),
+      None => Ok(FrameIteratorResult {
+        done: true,
+        value: None,
+      }),
+    }
+  }
+}
+
+#[napi(object)]
+#[derive(Clone)]
+pub struct Frame {
+  /// Absent when the frame is not preceded by a Graphic Control Extension, e.g. in GIF87a files
+  pub gcd: Option<GraphicsControlExtension>,
+  pub im: ImageDescriptor,
+  pub color_table: Vec<Color>,
+  /// Empty when parsed with ParseOptions.lazy, gif.indexStream decompresses it
+  pub index_stream: Vec<u8>,
+  /// Present when the frame is drawn by a Plain Text Extension instead of an image
+  pub plain_text: Option<PlainTextExtension>,
+}
+
+impl From<&crate::Frame> for Frame {
+  fn from(frame: &crate::Frame) -> Frame {
+    Frame {
+      gcd: frame.gcd.clone(),
+      im: frame.im.clone(),
+      color_table: frame.color_table.clone(),
+      index_stream: frame.index_stream.clone(),
+      plain_text: frame.plain_text.clone(),
+    }
+  }
+}
+
+/// A frame as the area it draws onto the canvas, returned by gif.decodeDeltas
+#[napi(object)]
+pub struct FrameDelta {
+  /// Position of the area on the canvas, clipped to the logical screen
+  pub left: u32,
+  pub top: u32,
+  pub width: u32,
+  pub height: u32,
+  /// RGBA of the area, transparent black where the frame leaves the canvas as it is
+  pub pixels: Buffer,
+  /// What to do with the area once the frame was displayed, `keep` it, clear it to the `background` or restore it to what it was before the frame, the `previous` pixels
+  #[napi(ts_type = "'keep' | 'background' | 'previous'")]
+  pub disposal: String,
+}
+
+impl From<crate::FrameDelta> for FrameDelta {
+  fn from(delta: crate::FrameDelta) -> FrameDelta {
+    FrameDelta {
+      left: delta.left,
+      top: delta.top,
+      width: delta.width,
+      height: delta.height,
+      pixels: Buffer::from(delta.pixels),
+      disposal: match delta.disposal {
+        crate::Disposal::Keep => "keep",
+        crate::Disposal::Background => "background",
+        crate::Disposal::Previous => "previous",
+      }
+      .to_string(),
+    }
+  }
+}
+
+#[napi]
+pub struct Decoder {}
+
+#[napi]
+impl Decoder {
+  #[napi]
+  pub fn decode_path(
+    env: Env,
+    file_path: String,
+    parse_options: Option<ParseOptions>,
+  ) -> napi::Result<Gif> {
+    match crate::Decoder::decode_path(file_path, &parse_options.unwrap_or_default()) {
+      Ok(gif) => Ok(Gif::from(gif)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Gifs parsed with ParseOptions.lazy read the buffer in place, so it must not be modified while the Gif is used
+  #[napi]
+  pub fn decode_buffer(
+    env: Env,
+    buffer: Buffer,
+    parse_options: Option<ParseOptions>,
+  ) -> napi::Result<Gif> {
+    match crate::Decoder::decode_owned(BufferContents(buffer), &parse_options.unwrap_or_default()) {
+      Ok(gif) => Ok(Gif::from(gif)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Same as decodePath, on the libuv threadpool
+  #[napi]
+  pub fn decode_path_async(
+    env: Env,
+    file_path: String,
+    parse_options: Option<ParseOptions>,
+    control: Option<DecodeControl>,
+  ) -> napi::Result<AsyncTask<DecodePathTask>> {
+    let (control, abort_listener) = DecodeControl::into_control(control, env)?;
+    Ok(AsyncTask::new(DecodePathTask {
+      file_path,
+      parse_options: parse_options.unwrap_or_default(),
+      control,
+      abort_listener,
+    }))
+  }
+
+  /// Same as decodeBuffer, on the libuv threadpool, the buffer is read in place so it must not be modified until the promise settles
+  #[napi]
+  pub fn decode_buffer_async(
+    env: Env,
+    buffer: Buffer,
+    parse_options: Option<ParseOptions>,
+    control: Option<DecodeControl>,
+  ) -> napi::Result<AsyncTask<DecodeBufferTask>> {
+    let (control, abort_listener) = DecodeControl::into_control(control, env)?;
+    Ok(AsyncTask::new(DecodeBufferTask {
+      buffer,
+      parse_options: parse_options.unwrap_or_default(),
+      control,
+      abort_listener,
+    }))
+  }
+
+  /// Reads the dimensions, frame count, duration and loop count without decompressing the image data
+  #[napi]
+  pub fn probe_path(
+    env: Env,
+    file_path: String,
+    parse_options: Option<ParseOptions>,
+  ) -> napi::Result<GifInfo> {
+    match crate::Decoder::probe_path(file_path, &parse_options.unwrap_or_default()) {
+      Ok(gif_info) => Ok(gif_info),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+
+  /// Reads the dimensions, frame count, duration and loop count without decompressing the image data
+  #[napi]
+  pub fn probe_buffer(
+    env: Env,
+    buffer: Buffer,
+    parse_options: Option<ParseOptions>,
+  ) -> napi::Result<GifInfo> {
+    match crate::Decoder::probe_buffer(&buffer, &parse_options.unwrap_or_default()) {
+      Ok(gif_info) => Ok(gif_info),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+}
+
+/// Something the StreamingDecoder finished reading, only the fields of its kind are present
+#[napi(object)]
+pub struct StreamEvent {
+  /// `header` | `applicationExtension` | `comment` | `unknownExtension` | `frame` | `end`
+  pub kind: String,
+  pub version: Option<String>,
+  pub lsd: Option<LogicalScreenDescriptor>,
+  pub global_table: Option<Vec<Color>>,
+  pub application_extension: Option<ApplicationExtension>,
+  pub comment: Option<CommentExtension>,
+  pub unknown_extension: Option<UnknownExtension>,
+  pub frame_index: Option<u32>,
+  pub frame: Option<Frame>,
+}
+
+impl StreamEvent {
+  fn new(kind: &str) -> StreamEvent {
+    StreamEvent {
+      kind: kind.to_string(),
+      version: None,
+      lsd: None,
+      global_table: None,
+      application_extension: None,
+      comment: None,
+      unknown_extension: None,
+      frame_index: None,
+      frame: None,
+    }
+  }
+}
+
+#[napi]
+pub struct StreamingDecoder {
+  parse_options: ParseOptions,
+  decoder: crate::StreamingDecoder,
+}
+
+#[napi]
+impl StreamingDecoder {
+  #[napi(constructor)]
+  pub fn new(parse_options: Option<ParseOptions>) -> StreamingDecoder {
+    let parse_options = parse_options.unwrap_or_default();
+    StreamingDecoder {
+      decoder: crate::StreamingDecoder::new(&parse_options),
+      parse_options,
+    }
+  }
+
+  /// Appends the chunk and returns the events of every block that is now complete
+  #[napi]
+  pub fn push(&mut self, env: Env, chunk: Buffer) -> napi::Result<Vec<StreamEvent>> {
+    let events = match self.decoder.push(&chunk) {
+      Ok(events) => events,
+      Err(error) => return Err(to_napi_error(env, error)),
+    };
+    let gif = match self.decoder.gif() {
+      Some(gif) => gif,
+      None => return Ok(Vec::new()),
+    };
+    Ok(
+      events
+        .into_iter()
+        .map(|event| match event {
+          crate::StreamEvent::Header => StreamEvent {
+            version: Some(gif.version.clone()),
+            lsd: Some(gif.lsd.clone()),
+            global_table: Some(gif.global_table.clone()),
+            ..StreamEvent::new("header")
+          },
+          crate::StreamEvent::ApplicationExtension(index) => StreamEvent {
+            application_extension: Some(gif.application_extensions[index].clone()),
+            ..StreamEvent::new("applicationExtension")
+          },
+          crate::StreamEvent::Comment(index) => StreamEvent {
+            comment: Some(gif.comments[index].clone()),
+            ..StreamEvent::new("comment")
+          },
+          crate::StreamEvent::UnknownExtension(index) => StreamEvent {
+            unknown_extension: Some(gif.unknown_extensions[index].clone()),
+            ..StreamEvent::new("unknownExtension")
+          },
+          crate::StreamEvent::Frame(index) => StreamEvent {
+            frame_index: Some(index as u32),
+            frame: Some(Frame::from(&gif.frames[index])),
+            ..StreamEvent::new("frame")
+          },
+          crate::StreamEvent::End => StreamEvent::new("end"),
+        })
+        .collect(),
+    )
+  }
+
+  /// Returns the Gif and resets the decoder, throws when the data is incomplete
+  #[napi]
+  pub fn finish(&mut self, env: Env) -> napi::Result<Gif> {
+    let decoder = std::mem::replace(
+      &mut self.decoder,
+      crate::StreamingDecoder::new(&self.parse_options),
+    );
+    match decoder.finish() {
+      Ok(gif) => Ok(Gif::from(gif)),
+      Err(error) => Err(to_napi_error(env, error)),
+    }
+  }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use crate::compositor::{self, DISPOSAL_BACKGROUND, DISPOSAL_PREVIOUS};
+use crate::control::DecodeControl;
+use crate::gif::{DecoderOptions, Frame, Gif};
+use crate::lzw;
+
+/// Default of DecoderOptions.checkpointBudget, 16 MiB
+pub(crate) const DEFAULT_CHECKPOINT_BUDGET: u32 = 16 * 1024 * 1024;
+
+/// Frames `Gif::decode_frame` can start compositing from instead of the first frame
+#[derive(Debug, Clone)]
+pub(crate) struct SeekIndex {
+  /// Options the index was built for, it is built again when compositing with other ones
+  decoder_options: DecoderOptions,
+  /// Frames composited the same onto an empty canvas as onto the previous frames, sorted
+  keyframes: Vec<usize>,
+  /// Canvas each frame is drawn onto, taken every `interval` frames while seeking
+  checkpoints: BTreeMap<usize, Vec<u8>>,
+  /// Absent when not even one canvas fits in DecoderOptions.checkpointBudget
+  interval: Option<usize>,
+}
+
+impl SeekIndex {
+  pub(crate) fn new(gif: &Gif, decoder_options: &DecoderOptions) -> SeekIndex {
+    let mut keyframes = Vec::new();
+    // Whether the canvas the frame is drawn onto only has the background
+    let mut is_canvas_empty = true;
+    let is_independent = decoder_options.raw_decode || decoder_options.disable_disposal_methods;
+    for (frame_index, frame) in gif.frames.iter().enumerate() {
+      let disposal_method = compositor::disposal_method(frame, decoder_options);
+      if is_independent
+        || is_canvas_empty
+        || (disposal_method != DISPOSAL_PREVIOUS && is_opaque(gif, frame_index, frame))
+      {
+        keyframes.push(frame_index);
+      }
+      is_canvas_empty = match disposal_method {
+        DISPOSAL_BACKGROUND => is_canvas_empty || covers_canvas(gif, frame),
+        DISPOSAL_PREVIOUS => is_canvas_empty,
+        _ => false,
+      };
+    }
+
+    let budget = decoder_options
+      .checkpoint_budget
+      .unwrap_or(DEFAULT_CHECKPOINT_BUDGET) as usize;
+    let canvas_size = (gif.lsd.width * gif.lsd.height) as usize * 4;
+    let interval = match budget.checked_div(canvas_size) {
+      Some(0) | None => None,
+      Some(checkpoints) => Some(gif.frames.len().div_ceil(checkpoints).max(1)),
+    };
+
+    SeekIndex {
+      decoder_options: decoder_options.clone(),
+      keyframes,
+      checkpoints: BTreeMap::new(),
+      interval,
+    }
+  }
+
+  /// Whether the index was built for the same compositing as these options
+  pub(crate) fn matches(&self, decoder_options: &DecoderOptions) -> bool {
+    compositor::composites_alike(&self.decoder_options, decoder_options)
+      && self.decoder_options.checkpoint_budget == decoder_options.checkpoint_budget
+  }
+
+  pub(crate) fn keyframes(&self) -> &[usize] {
+    &self.keyframes
+  }
+
+  /// Closest frame up to `frame_index` to start compositing from, with the canvas it is drawn onto, empty when absent
+  pub(crate) fn start(&self, frame_index: usize) -> (usize, Option<&Vec<u8>>) {
+    let keyframe = match self
+      .keyframes
+      .partition_point(|keyframe| *keyframe <= frame_index)
+    {
+      0 => 0,
+      position => self.keyframes[position - 1],
+    };
+    match self.checkpoints.range(..=frame_index).next_back() {
+      Some((checkpoint, canvas)) if *checkpoint > keyframe => (*checkpoint, Some(canvas)),
+      _ => (keyframe, None),
+    }
+  }
+
+  /// Number of checkpoints and their bytes
+  pub(crate) fn checkpoint_stats(&self) -> (usize, usize) {
+    (
+      self.checkpoints.len(),
+      self.checkpoints.values().map(Vec::len).sum(),
+    )
+  }
+
+  pub(crate) fn clear_checkpoints(&mut self) {
+    self.checkpoints.clear();
+  }
+
+  /// Keeps the canvas the frame is drawn onto when it is due for a checkpoint
+  pub(crate) fn record(&mut self, frame_index: usize, canvas: &[u8]) {
+    let interval = match self.interval {
+      Some(interval) => interval,
+      None => return,
+    };
+    if !frame_index.is_multiple_of(interval) || self.keyframes.binary_search(&frame_index).is_ok() {
+      return;
+    }
+    self
+      .checkpoints
+      .entry(frame_index)
+      .or_insert_with(|| canvas.to_vec());
+  }
+}
+
+fn covers_canvas(gif: &Gif, frame: &Frame) -> bool {
+  let (left, top, width, height) = compositor::frame_rect(frame);
+  left == 0 && top == 0 && width >= gif.lsd.width && height >= gif.lsd.height
+}
+
+/// Whether drawing the frame replaces every pixel of the canvas, the index streams of lazy frames are decompressed
+/// to know it and dropped again, so they are only kept for the frames that are decoded
+fn is_opaque(gif: &Gif, frame_index: usize, frame: &Frame) -> bool {
+  if frame.plain_text.is_some() || !covers_canvas(gif, frame) {
+    return false;
+  }
+  let decompressed;
+  let index_stream = match &gif.contents {
+    Some(contents) if frame.index_stream.is_empty() && !frame.data_ranges.is_empty() => {
+      // A frame failing to decompress is no keyframe, decoding it reports the error
+      match lzw::decode_index_stream(
+        frame,
+        (**contents).as_ref(),
+        frame_index,
+        &DecodeControl::default(),
+      ) {
+        Ok(index_stream) => {
+          decompressed = index_stream;
+          &decompressed
+        }
+        Err(_) => return false,
+      }
+    }
+    _ => &frame.index_stream,
+  };
+  let pixels = (frame.im.width * frame.im.height) as usize;
+  index_stream.len() >= pixels
+    && index_stream[..pixels].iter().all(|color_index| {
+      (*color_index as usize) < frame.color_table.len() && !frame.is_transparent_index(*color_index)
+    })
+}
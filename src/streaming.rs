@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use crate::control::DecodeControl;
+use crate::decoder::Decoder;
+use crate::error::{Error, Result};
+use crate::gif::{Gif, GraphicsControlExtension, ParseOptions};
+
+/// Something the decoder finished reading, the indexes point into the `Gif` returned by `StreamingDecoder::gif`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEvent {
+  /// The header, logical screen descriptor and global color table
+  Header,
+  ApplicationExtension(usize),
+  Comment(usize),
+  UnknownExtension(usize),
+  /// An image or plain text frame, its index stream is fully decoded unless ParseOptions.lazy is set,
+  /// lazy frames are only decompressed when the Gif returned by `StreamingDecoder::finish` decodes them
+  Frame(usize),
+  /// The trailer, any data pushed afterwards is ignored
+  End,
+}
+
+/// Push based decoder, accepts the file in chunks of any size and reads every block as soon as it is complete
+pub struct StreamingDecoder {
+  parse_options: ParseOptions,
+  contents: Vec<u8>,
+  offset: usize,
+  gif: Option<Gif>,
+  pending_gcd: Option<GraphicsControlExtension>,
+  done: bool,
+}
+
+impl StreamingDecoder {
+  pub fn new(parse_options: &ParseOptions) -> StreamingDecoder {
+    StreamingDecoder {
+      // Frames are emitted one at a time, so they are decompressed as they arrive
+      parse_options: ParseOptions {
+        parallel: None,
+        ..parse_options.clone()
+      },
+      contents: Vec::new(),
+      offset: 0,
+      gif: None,
+      pending_gcd: None,
+      done: false,
+    }
+  }
+
+  /// Appends the chunk and reads every block that is now complete
+  pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<StreamEvent>> {
+    let mut events = Vec::new();
+    if self.done {
+      return Ok(events);
+    }
+    self.contents.extend_from_slice(chunk);
+
+    if self.gif.is_none() {
+      match Decoder::decode_header(&self.contents, &self.parse_options) {
+        Ok((gif, offset)) => {
+          self.gif = Some(gif);
+          self.offset = offset;
+          events.push(StreamEvent::Header);
+        }
+        // Wait for the rest of the header
+        Err(Error::TruncatedBlock { .. }) => return Ok(events),
+        Err(error) => return Err(error),
+      }
+    }
+
+    let gif = match &mut self.gif {
+      Some(gif) => gif,
+      None => return Ok(events),
+    };
+    while !self.done {
+      // Blocks are only read once complete, the handlers do not support resuming
+      let block_length = match Self::block_length(&self.contents, self.offset) {
+        Some(block_length) => block_length,
+        None => break,
+      };
+      let block_end = self.offset + block_length;
+      match Decoder::decode_block(
+        &mut self.offset,
+        gif,
+        &self.contents[..block_end],
+        &mut self.pending_gcd,
+        &self.parse_options,
+        &DecodeControl::default(),
+      ) {
+        Ok(Some(event)) => {
+          self.done = event == StreamEvent::End;
+          events.push(event);
+        }
+        Ok(None) => {}
+        Err(error) => return Err(error),
+      }
+    }
+    Ok(events)
+  }
+
+  /// The Gif read so far, `None` until the header is complete
+  pub fn gif(&self) -> Option<&Gif> {
+    self.gif.as_ref()
+  }
+
+  /// Whether the trailer was read
+  pub fn is_done(&self) -> bool {
+    self.done
+  }
+
+  /// Returns the Gif, fails with the same error as `Decoder::decode_buffer` when the data is incomplete
+  pub fn finish(mut self) -> Result<Gif> {
+    let mut gif = match self.gif.take() {
+      Some(gif) => gif,
+      None => Decoder::decode_header(&self.contents, &self.parse_options)?.0,
+    };
+    while !self.done {
+      match Decoder::decode_block(
+        &mut self.offset,
+        &mut gif,
+        &self.contents,
+        &mut self.pending_gcd,
+        &self.parse_options,
+        &DecodeControl::default(),
+      ) {
+        Ok(event) => self.done = event == Some(StreamEvent::End),
+        Err(error) => return Err(error),
+      }
+    }
+    if self.parse_options.lazy.unwrap_or(false) {
+      gif.contents = Some(Arc::new(self.contents));
+    }
+    Ok(gif)
+  }
+
+  /// Length of the block at `offset`, `None` when it is not complete yet
+  fn block_length(contents: &[u8], offset: usize) -> Option<usize> {
+    let mut end = offset + 1;
+    match contents.get(offset)? {
+      0x2C => {
+        // Image Descriptor, Local Color Table and LZW minimum code size
+        let packed_field = *contents.get(offset + 9)?;
+        end += 9;
+        if (packed_field & 0b1000_0000) != 0 {
+          end += (3 * 2) << (packed_field & 0b0000_0111);
+        }
+        end += 1;
+      }
+      // Label, the rest of any extension is made of sub-blocks
+      0x21 => end += 1,
+      _ => return Some(1),
+    }
+    loop {
+      let data_sub_blocks_count = *contents.get(end)? as usize;
+      end += 1 + data_sub_blocks_count;
+      if data_sub_blocks_count == 0 {
+        break;
+      }
+    }
+    (contents.len() >= end).then_some(end - offset)
+  }
+}
@@ -0,0 +1,470 @@
+use crate::{
+  Background, Decoder, DecoderLimits, DecoderOptions, Disposal, Error, FrameDelta, Gif,
+  ParseOptions, StreamEvent, StreamingDecoder,
+};
+
+const FIXTURES: [&str; 5] = [
+  "sample_2_animation.gif",
+  "BBoomer.gif",
+  "forsenParty.gif",
+  "shadowchanHeart.0",
+  "disposal.gif",
+];
+
+fn read(file: &str) -> Vec<u8> {
+  std::fs::read(format!("{}/gifs/{}", env!("CARGO_MANIFEST_DIR"), file)).unwrap()
+}
+
+fn decode(file: &str) -> Gif {
+  Decoder::decode_buffer(&read(file), &ParseOptions::default()).unwrap()
+}
+
+/// `header` followed by `frame` `count` times and the trailer
+fn repeat_frame(header: &[u8], frame: &[u8], count: usize) -> Vec<u8> {
+  let mut contents = header.to_vec();
+  for _ in 0..count {
+    contents.extend_from_slice(frame);
+  }
+  contents.push(0x3B);
+  contents
+}
+
+/// 1x1 image with the first colour of its 4 colour code table
+const TINY_FRAME: [u8; 15] = [
+  0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00,
+];
+
+#[test]
+fn decode_owned_decodes_like_decode_buffer() {
+  for file in FIXTURES {
+    let lazy = ParseOptions {
+      lazy: Some(true),
+      ..Default::default()
+    };
+    let mut owned = Decoder::decode_owned(read(file), &lazy).unwrap();
+    let mut expected = decode(file);
+    let decoder_options = DecoderOptions::default();
+    assert_eq!(
+      owned.decode_frames(&decoder_options).unwrap(),
+      expected.decode_frames(&decoder_options).unwrap(),
+      "{}",
+      file
+    );
+  }
+}
+
+#[test]
+fn streaming_decoder_emits_every_block() {
+  let contents = read("BBoomer.gif");
+  let mut decoder = StreamingDecoder::new(&ParseOptions::default());
+  let mut events = Vec::new();
+  for chunk in contents.chunks(7) {
+    events.extend(decoder.push(chunk).unwrap());
+  }
+  assert!(decoder.is_done());
+  let mut gif = decoder.finish().unwrap();
+  let mut expected = decode("BBoomer.gif");
+
+  assert_eq!(events.first(), Some(&StreamEvent::Header));
+  assert_eq!(events.last(), Some(&StreamEvent::End));
+  let frames: Vec<usize> = events
+    .iter()
+    .filter_map(|event| match event {
+      StreamEvent::Frame(frame_index) => Some(*frame_index),
+      _ => None,
+    })
+    .collect();
+  assert_eq!(frames, (0..expected.frames.len()).collect::<Vec<_>>());
+  let decoder_options = DecoderOptions::default();
+  assert_eq!(
+    gif.decode_frames(&decoder_options).unwrap(),
+    expected.decode_frames(&decoder_options).unwrap()
+  );
+}
+
+#[test]
+fn streaming_decoder_reports_truncated_files() {
+  let contents = read("sample_2_animation.gif");
+  let mut decoder = StreamingDecoder::new(&ParseOptions::default());
+  decoder.push(&contents[..contents.len() / 2]).unwrap();
+  assert!(!decoder.is_done());
+  assert_eq!(
+    decoder.finish().unwrap_err().code(),
+    "ERR_GIF_TRUNCATED_BLOCK"
+  );
+}
+
+#[test]
+fn frame_iter_yields_the_frames_of_decode_frames() {
+  for file in FIXTURES {
+    let decoder_options = DecoderOptions::default();
+    let expected = decode(file).decode_frames(&decoder_options).unwrap();
+    let mut gif = decode(file);
+    let iter = gif.iter_frames(&decoder_options);
+    assert_eq!(iter.size_hint(), (expected.len(), Some(expected.len())));
+    let frames: Vec<Vec<u8>> = iter.map(Result::unwrap).collect();
+    assert_eq!(frames, expected, "{}", file);
+    assert!(gif.frames.iter().all(|frame| frame.cached_frame.is_none()));
+  }
+}
+
+#[test]
+fn decode_frame_seeks_to_the_frames_of_decode_frames() {
+  let decoder_options = DecoderOptions {
+    checkpoint_budget: Some(0),
+    ..Default::default()
+  };
+  let expected = decode("forsenParty.gif")
+    .decode_frames(&decoder_options)
+    .unwrap();
+  let mut gif = decode("forsenParty.gif");
+  for frame_index in (0..expected.len()).rev().step_by(7) {
+    assert_eq!(
+      gif.decode_frame(frame_index, &decoder_options).unwrap(),
+      expected[frame_index]
+    );
+  }
+  assert!(matches!(
+    gif.decode_frame(expected.len(), &decoder_options),
+    Err(Error::FrameOutOfBounds { .. })
+  ));
+
+  // Opaque frames without a Graphic Control Extension are all keyframes, lazy or not,
+  // 1x1 logical screen with a global table of 4 colours
+  let mut header = vec![
+    b'G', b'I', b'F', b'8', b'9', b'a', 0x01, 0x00, 0x01, 0x00, 0x81, 0x00, 0x00,
+  ];
+  header.extend_from_slice(&[0xFF; 12]);
+  let contents = repeat_frame(&header, &TINY_FRAME, 5);
+  let lazy = ParseOptions {
+    lazy: Some(true),
+    ..Default::default()
+  };
+  let mut eager = Decoder::decode_buffer(&contents, &ParseOptions::default()).unwrap();
+  let mut gif = Decoder::decode_buffer(&contents, &lazy).unwrap();
+  assert_eq!(eager.keyframes(&decoder_options), [0, 1, 2, 3, 4]);
+  assert_eq!(gif.keyframes(&decoder_options), [0, 1, 2, 3, 4]);
+  assert!(gif.frames.iter().all(|frame| frame.index_stream.is_empty()));
+  let expected = eager.decode_frames(&decoder_options).unwrap();
+  for frame_index in (0..expected.len()).rev() {
+    assert_eq!(
+      gif.decode_frame(frame_index, &decoder_options).unwrap(),
+      expected[frame_index]
+    );
+  }
+}
+
+#[test]
+fn decode_frame_only_decompresses_the_frames_from_the_closest_keyframe() {
+  let lazy = ParseOptions {
+    lazy: Some(true),
+    ..Default::default()
+  };
+  let contents = read("YESITHINKSO.gif");
+  let mut gif = Decoder::decode_buffer(&contents, &lazy).unwrap();
+  // Room for 16 canvases, one every 11 frames
+  let decoder_options = DecoderOptions {
+    store_cache: false,
+    checkpoint_budget: Some(gif.lsd.width * gif.lsd.height * 4 * 16),
+    ..Default::default()
+  };
+  gif.decode_frame(161, &decoder_options).unwrap();
+  assert!(gif.frames[6].index_stream.is_empty());
+  assert!(!gif.frames[7].index_stream.is_empty());
+
+  let mut gif = Decoder::decode_buffer(&contents, &lazy).unwrap();
+  let free_index_streams = DecoderOptions {
+    free_index_streams: Some(true),
+    ..decoder_options.clone()
+  };
+  gif.decode_frame(161, &free_index_streams).unwrap();
+  gif.decode_frame(150, &decoder_options).unwrap();
+  assert!(gif.frames[142].index_stream.is_empty());
+  assert!(!gif.frames[143].index_stream.is_empty());
+}
+
+/// Draws the deltas the way `decode_frames` composites the frames
+fn composite(width: u32, height: u32, deltas: &[FrameDelta]) -> Vec<Vec<u8>> {
+  let mut canvas = vec![0; (width * height) as usize * 4];
+  let mut frames = Vec::new();
+  for delta in deltas {
+    let previous = canvas.clone();
+    for y in 0..delta.height {
+      for x in 0..delta.width {
+        let pixel = ((y * delta.width + x) * 4) as usize;
+        if delta.pixels[pixel + 3] != 0 {
+          let canvas_pixel = (((delta.top + y) * width + delta.left + x) * 4) as usize;
+          canvas[canvas_pixel..canvas_pixel + 4].copy_from_slice(&delta.pixels[pixel..pixel + 4]);
+        }
+      }
+    }
+    frames.push(canvas.clone());
+    for y in delta.top..delta.top + delta.height {
+      for x in delta.left..delta.left + delta.width {
+        let canvas_pixel = ((y * width + x) * 4) as usize;
+        match delta.disposal {
+          Disposal::Keep => {}
+          Disposal::Background => canvas[canvas_pixel..canvas_pixel + 4].fill(0),
+          Disposal::Previous => canvas[canvas_pixel..canvas_pixel + 4]
+            .copy_from_slice(&previous[canvas_pixel..canvas_pixel + 4]),
+        }
+      }
+    }
+  }
+  frames
+}
+
+#[test]
+fn compositing_deltas_gives_the_frames_of_decode_frames() {
+  for file in FIXTURES {
+    let decoder_options = DecoderOptions::default();
+    let mut gif = decode(file);
+    let deltas = gif.decode_deltas(&decoder_options).unwrap();
+    assert_eq!(deltas.len(), gif.frames.len());
+    assert_eq!(
+      composite(gif.lsd.width, gif.lsd.height, &deltas),
+      gif.decode_frames(&decoder_options).unwrap(),
+      "{}",
+      file
+    );
+  }
+}
+
+#[test]
+fn disposal_deltas_follow_the_disposal_methods() {
+  let mut gif = decode("disposal.gif");
+  let disposals: Vec<Disposal> = gif
+    .decode_deltas(&DecoderOptions::default())
+    .unwrap()
+    .iter()
+    .map(|delta| delta.disposal)
+    .collect();
+  assert_eq!(
+    disposals,
+    [
+      Disposal::Keep,
+      Disposal::Previous,
+      Disposal::Background,
+      Disposal::Keep
+    ]
+  );
+
+  let without_previous = DecoderOptions {
+    implement_disposal_previous: false,
+    ..Default::default()
+  };
+  assert_eq!(
+    gif.decode_delta(1, &without_previous).unwrap().disposal,
+    Disposal::Keep
+  );
+}
+
+#[test]
+fn background_fills_what_the_frames_leave_transparent() {
+  let color = [255, 0, 255, 255];
+  let transparent = decode("disposal.gif")
+    .decode_frames(&DecoderOptions::default())
+    .unwrap();
+  let custom = decode("disposal.gif")
+    .decode_frames(&DecoderOptions {
+      background: Some(Background::Custom(color)),
+      ..Default::default()
+    })
+    .unwrap();
+  let mut filled = 0;
+  for (transparent, custom) in transparent.iter().zip(&custom) {
+    for (transparent, custom) in transparent.chunks(4).zip(custom.chunks(4)) {
+      if transparent[3] == 0 {
+        assert_eq!(custom, color);
+        filled += 1;
+      } else {
+        assert_eq!(custom, transparent);
+      }
+    }
+  }
+  assert!(filled > 0);
+}
+
+#[test]
+fn limits_are_checked_while_parsing() {
+  let contents = read("sample_2_animation.gif");
+  let decode_with = |limits: DecoderLimits| {
+    Decoder::decode_buffer(
+      &contents,
+      &ParseOptions {
+        limits: Some(limits),
+        ..Default::default()
+      },
+    )
+  };
+  match decode_with(DecoderLimits {
+    max_width: Some(10),
+    ..Default::default()
+  }) {
+    Err(Error::LimitsExceeded {
+      limit,
+      offset,
+      frame_index,
+    }) => assert_eq!((limit, offset, frame_index), ("max_width", 6, None)),
+    result => panic!("expected max_width to be exceeded, got {:?}", result),
+  }
+  match decode_with(DecoderLimits {
+    max_frames: Some(2),
+    ..Default::default()
+  }) {
+    Err(error) => assert_eq!(
+      (error.code(), error.frame_index()),
+      ("ERR_GIF_LIMITS_EXCEEDED", Some(2))
+    ),
+    Ok(_) => panic!("expected max_frames to be exceeded"),
+  }
+  let gif = decode_with(DecoderLimits {
+    max_width: Some(11),
+    max_height: Some(29),
+    max_pixels: Some(11 * 29),
+    max_frames: Some(3),
+    max_decoded_bytes: None,
+    max_compression_ratio: Some(1000.0),
+  })
+  .unwrap();
+  assert_eq!(gif.frames.len(), 3);
+}
+
+#[test]
+fn max_decoded_bytes_counts_a_canvas_per_frame() {
+  // 2000x2000 logical screen, every 1x1 frame is composited onto a 16 MB canvas
+  let header = [
+    b'G', b'I', b'F', b'8', b'9', b'a', 0xD0, 0x07, 0xD0, 0x07, 0x00, 0x00, 0x00,
+  ];
+  let contents = repeat_frame(&header, &TINY_FRAME, 100);
+  assert_eq!(
+    Decoder::decode_buffer(&contents, &ParseOptions::default())
+      .unwrap()
+      .frames
+      .len(),
+    100
+  );
+  let limited = ParseOptions {
+    limits: Some(DecoderLimits {
+      max_decoded_bytes: Some(20_000_000),
+      ..Default::default()
+    }),
+    ..Default::default()
+  };
+  let error = Decoder::decode_buffer(&contents, &limited).unwrap_err();
+  assert_eq!(
+    error.to_string(),
+    "Decoder limit max_decoded_bytes exceeded at offset 28 in frame 1"
+  );
+}
+
+#[test]
+fn probe_saturates_the_total_duration() {
+  let header = [
+    b'G', b'I', b'F', b'8', b'9', b'a', 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+  ];
+  // Graphic Control Extension with the longest delay before every frame
+  let mut frame = vec![0x21, 0xF9, 0x04, 0x00, 0xFF, 0xFF, 0x00, 0x00];
+  frame.extend_from_slice(&TINY_FRAME);
+  let contents = repeat_frame(&header, &frame, 7000);
+  let info = Decoder::probe_buffer(&contents, &ParseOptions::default()).unwrap();
+  assert_eq!(info.frame_count, 7000);
+  assert_eq!(info.total_duration, u32::MAX);
+}
+
+#[test]
+fn corrupted_files_fail_instead_of_panicking() {
+  let contents = read("gif87a.gif");
+
+  // Application identifiers that are not UTF-8, split between the identifier and authentication code
+  for (identifier, expected) in [
+    ([0xFF; 11], ["\u{FFFD}".repeat(8), "\u{FFFD}".repeat(3)]),
+    (
+      *b"ABCDEFG\xC3\xA9AB",
+      ["ABCDEFG\u{FFFD}".to_string(), "\u{FFFD}AB".to_string()],
+    ),
+  ] {
+    let mut corrupted = contents[..34].to_vec();
+    corrupted.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+    corrupted.extend_from_slice(&identifier);
+    corrupted.extend_from_slice(&[0x00, 0x3B]);
+    let gif = Decoder::decode_buffer(&corrupted, &ParseOptions::default()).unwrap();
+    let extension = &gif.application_extensions[0];
+    assert_eq!(
+      [&extension.identifier, &extension.authentication_code],
+      [&expected[0], &expected[1]]
+    );
+  }
+
+  for code_size in [12, 31, 255] {
+    let mut corrupted = contents.clone();
+    corrupted[29] = code_size;
+    assert_eq!(
+      Decoder::decode_buffer(&corrupted, &ParseOptions::default())
+        .unwrap_err()
+        .code(),
+      "ERR_GIF_INVALID_LZW_MINIMUM_CODE_SIZE"
+    );
+  }
+
+  // 8x1 image of the clear code then 6 three times, 6 is not in the code table right after the clear code
+  let mut corrupted = contents[..30].to_vec();
+  corrupted[24] = 8;
+  corrupted.extend_from_slice(&[0x02, 0xB4, 0x0D, 0x00, 0x3B]);
+  assert_eq!(
+    Decoder::decode_buffer(&corrupted, &ParseOptions::default())
+      .unwrap_err()
+      .code(),
+    "ERR_GIF_INVALID_LZW_CODE"
+  );
+  let mut decoder = StreamingDecoder::new(&ParseOptions::default());
+  assert_eq!(
+    decoder.push(&corrupted).unwrap_err().code(),
+    "ERR_GIF_INVALID_LZW_CODE"
+  );
+
+  for length in 0..contents.len() - 1 {
+    assert!(Decoder::decode_buffer(&contents[..length], &ParseOptions::default()).is_err());
+  }
+}
+
+#[test]
+fn padding_between_blocks_is_skipped() {
+  let contents = read("gif87a.gif");
+  let mut padded = contents[..34].to_vec();
+  padded.extend_from_slice(&[0x00, 0x00]);
+  padded.extend_from_slice(&contents[34..]);
+  assert_eq!(
+    Decoder::decode_buffer(&padded, &ParseOptions::default())
+      .unwrap()
+      .frames
+      .len(),
+    1
+  );
+  let mut decoder = StreamingDecoder::new(&ParseOptions::default());
+  decoder.push(&padded).unwrap();
+  assert!(decoder.is_done());
+}
+
+#[test]
+fn streaming_decoder_decompresses_lazy_frames_once_finished() {
+  let contents = read("sample_2_animation.gif");
+  let mut decoder = StreamingDecoder::new(&ParseOptions {
+    lazy: Some(true),
+    ..Default::default()
+  });
+  decoder.push(&contents).unwrap();
+  let streamed = decoder.gif().unwrap();
+  assert!(streamed
+    .frames
+    .iter()
+    .all(|frame| frame.index_stream.is_empty()));
+  let mut gif = decoder.finish().unwrap();
+  let decoder_options = DecoderOptions::default();
+  assert_eq!(
+    gif.decode_frames(&decoder_options).unwrap(),
+    decode("sample_2_animation.gif")
+      .decode_frames(&decoder_options)
+      .unwrap()
+  );
+}